@@ -19,6 +19,7 @@ struct Vote {
 
 event ValidatorRegistered(address addr, bytes bls12_381_public_key);
 event ValidatorResigned(address addr);
+event ValidatorPenalized(address addr, uint256 penaltiesCount);
 
 event Voted(address voter, address validator);
 event Unvoted(address voter, address validator);
@@ -42,6 +43,9 @@ struct Node {
 	address next;
 }
 
+// Sender of calls made by the protocol itself, e.g. to calculate the top validators
+address constant SYSTEM_ADDRESS = 0xffffFFFfFFffffffffffffffFfFFFfffFFFfFFfE;
+
 contract Consensus {
 	address immutable _owner;
 
@@ -53,6 +57,7 @@ contract Consensus {
 	address[] private _registeredValidators;
 
 	mapping(address => Vote) private _votes;
+	mapping(address => uint256) private _penaltiesCount;
 
 	address private _head;
 	mapping(address => address) private _topValidators;
@@ -63,8 +68,8 @@ contract Consensus {
 		_owner = msg.sender;
 	}
 
-	modifier onlyOwner() {
-		require(msg.sender == _owner, "Caller is not the contract owner");
+	modifier onlySystem() {
+		require(msg.sender == SYSTEM_ADDRESS, "Caller is not the system address");
 		_;
 	}
 
@@ -97,7 +102,7 @@ contract Consensus {
 		_topValidatorsCount = 0;
 	}
 
-	function calculateTopValidators(uint8 n) external onlyOwner {
+	function calculateTopValidators(uint8 n) external onlySystem {
 		shuffle();
 		deleteTopValidators();
 
@@ -269,6 +274,18 @@ contract Consensus {
 		emit ValidatorResigned(msg.sender);
 	}
 
+	function penalizeValidator(address addr) external onlySystem {
+		require(isValidatorRegistered(addr), "ValidatorData doesn't exists");
+
+		_penaltiesCount[addr] += 1;
+
+		emit ValidatorPenalized(addr, _penaltiesCount[addr]);
+	}
+
+	function penaltiesCount(address addr) public view returns (uint256) {
+		return _penaltiesCount[addr];
+	}
+
 	function isValidatorRegistered(address addr) public view returns (bool) {
 		return _hasRegisteredValidator[addr];
 	}
@@ -303,6 +320,10 @@ contract Consensus {
 		emit Voted(msg.sender, addr);
 	}
 
+	function getVote(address addr) public view returns (Vote memory) {
+		return _votes[addr];
+	}
+
 	function unvote() external {
 		Vote storage voter = _votes[msg.sender];
 		require(voter.validator != address(0), "TODO: not voted");
@@ -314,7 +335,7 @@ contract Consensus {
 		delete _votes[msg.sender];
 	}
 
-	function updateVoters(address[] calldata voters) external onlyOwner {
+	function updateVoters(address[] calldata voters) external onlySystem {
 		// TODO: limit number of voters per update?
 		for (uint i = 0; i < voters.length; i++) {
 			_updateVoter(voters[i]);
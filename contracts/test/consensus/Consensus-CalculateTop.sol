@@ -2,7 +2,7 @@
 pragma solidity ^0.8.13;
 
 import {Test, console} from "@forge-std/Test.sol";
-import {Consensus, ValidatorData, Validator} from "@contracts/consensus/Consensus.sol";
+import {Consensus, ValidatorData, Validator, SYSTEM_ADDRESS} from "@contracts/consensus/Consensus.sol";
 import {Base} from "./Base.sol";
 
 contract ConsensusTest is Base {
@@ -18,7 +18,9 @@ contract ConsensusTest is Base {
 		consensus.registerValidator(prepareBLSKey(addr));
 		vm.stopPrank();
 
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.calculateTopValidators(1);
+		vm.stopPrank();
 		Validator[] memory validators = consensus.getTopValidators();
 		assertEq(validators.length, 1);
 		assertEq(validators[0].addr, addr);
@@ -27,7 +29,7 @@ contract ConsensusTest is Base {
 	function test_should_allow_only_caller() public {
 		address addr = address(1);
 		vm.startPrank(addr);
-		vm.expectRevert("Caller is not the contract owner");
+		vm.expectRevert("Caller is not the system address");
 		consensus.calculateTopValidators(1);
 	}
 
@@ -39,7 +41,9 @@ contract ConsensusTest is Base {
 		consensus.resignValidator();
 		vm.stopPrank();
 
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.calculateTopValidators(1);
+		vm.stopPrank();
 		Validator[] memory validators = consensus.getTopValidators();
 		assertEq(validators.length, 0);
 	}
@@ -79,12 +83,16 @@ contract ConsensusTest is Base {
 
 		uint160 activeValidators = 53;
 
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.calculateTopValidators(uint8(activeValidators));
+		vm.stopPrank();
 		Validator[] memory validators = consensus.getTopValidators();
 		assertEq(validators.length, activeValidators);
 		assertEq(validators[activeValidators - 1].addr, highest);
 
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.calculateTopValidators(uint8(activeValidators));
+		vm.stopPrank();
 
 		validators = consensus.getTopValidators();
 		assertEq(validators.length, activeValidators);
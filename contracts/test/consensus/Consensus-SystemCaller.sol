@@ -0,0 +1,63 @@
+// SPDX-License-Identifier: GNU GENERAL PUBLIC LICENSE
+pragma solidity ^0.8.13;
+
+import {Consensus, SYSTEM_ADDRESS} from "@contracts/consensus/Consensus.sol";
+import {Base} from "./Base.sol";
+
+contract ConsensusSystemCallerTest is Base {
+	Consensus public consensus;
+
+	function setUp() public {
+		consensus = new Consensus();
+	}
+
+	function test_system_address_can_call_system_functions() public {
+		address addr = address(1);
+
+		vm.startPrank(addr);
+		consensus.registerValidator(prepareBLSKey(addr));
+		vm.stopPrank();
+
+		vm.startPrank(SYSTEM_ADDRESS);
+		consensus.calculateTopValidators(1);
+		consensus.updateVoters(new address[](0));
+		consensus.penalizeValidator(addr);
+		vm.stopPrank();
+
+		assertEq(consensus.getTopValidators().length, 1);
+		assertEq(consensus.penaltiesCount(addr), 1);
+	}
+
+	function test_owner_cannot_call_system_functions() public {
+		address addr = address(1);
+
+		vm.startPrank(addr);
+		consensus.registerValidator(prepareBLSKey(addr));
+		vm.stopPrank();
+
+		// the test contract deployed the consensus contract and is its owner
+		vm.expectRevert("Caller is not the system address");
+		consensus.calculateTopValidators(1);
+
+		vm.expectRevert("Caller is not the system address");
+		consensus.updateVoters(new address[](0));
+
+		vm.expectRevert("Caller is not the system address");
+		consensus.penalizeValidator(addr);
+	}
+
+	function test_other_callers_cannot_call_system_functions() public {
+		address addr = address(2);
+
+		vm.startPrank(addr);
+		vm.expectRevert("Caller is not the system address");
+		consensus.calculateTopValidators(1);
+
+		vm.expectRevert("Caller is not the system address");
+		consensus.updateVoters(new address[](0));
+
+		vm.expectRevert("Caller is not the system address");
+		consensus.penalizeValidator(addr);
+		vm.stopPrank();
+	}
+}
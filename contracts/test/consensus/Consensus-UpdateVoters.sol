@@ -15,7 +15,7 @@ contract ConsensusTest is Test {
 		address addr = address(1);
 		vm.startPrank(addr);
 		address[] memory voters = new address[](0);
-		vm.expectRevert("Caller is not the contract owner");
+		vm.expectRevert("Caller is not the system address");
 		consensus.updateVoters(voters);
 	}
 }
@@ -0,0 +1,53 @@
+// SPDX-License-Identifier: GNU GENERAL PUBLIC LICENSE
+pragma solidity ^0.8.13;
+
+import {Consensus, ValidatorPenalized, SYSTEM_ADDRESS} from "@contracts/consensus/Consensus.sol";
+import {Base} from "./Base.sol";
+
+contract ConsensusTest is Base {
+	Consensus public consensus;
+
+	function setUp() public {
+		consensus = new Consensus();
+	}
+
+	function test_penalize_validator_pass() public {
+		address addr = address(1);
+
+		vm.startPrank(addr);
+		consensus.registerValidator(prepareBLSKey(addr));
+		vm.stopPrank();
+
+		assertEq(consensus.penaltiesCount(addr), 0);
+
+		// Act
+		vm.startPrank(SYSTEM_ADDRESS);
+		vm.expectEmit(address(consensus));
+		emit ValidatorPenalized(addr, 1);
+		consensus.penalizeValidator(addr);
+
+		vm.expectEmit(address(consensus));
+		emit ValidatorPenalized(addr, 2);
+		consensus.penalizeValidator(addr);
+		vm.stopPrank();
+
+		// Assert
+		assertEq(consensus.penaltiesCount(addr), 2);
+	}
+
+	function test_penalize_validator_revert_if_caller_is_not_system() public {
+		address addr = address(1);
+
+		vm.startPrank(addr);
+		consensus.registerValidator(prepareBLSKey(addr));
+		vm.expectRevert("Caller is not the system address");
+		consensus.penalizeValidator(addr);
+	}
+
+	function test_penalize_validator_revert_if_not_registered() public {
+		vm.startPrank(SYSTEM_ADDRESS);
+		vm.expectRevert("ValidatorData doesn't exists");
+		consensus.penalizeValidator(address(1));
+		vm.stopPrank();
+	}
+}
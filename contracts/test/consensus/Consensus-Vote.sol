@@ -2,7 +2,7 @@
 pragma solidity ^0.8.13;
 
 import {Test, console} from "@forge-std/Test.sol";
-import {Consensus, ValidatorData, Validator, Unvoted, Voted} from "@contracts/consensus/Consensus.sol";
+import {Consensus, ValidatorData, Validator, Vote, Unvoted, Voted, SYSTEM_ADDRESS} from "@contracts/consensus/Consensus.sol";
 
 contract ConsensusTest is Test {
 	Consensus public consensus;
@@ -63,7 +63,9 @@ contract ConsensusTest is Test {
 
 		address[] memory voters = new address[](1);
 		voters[0] = voterAddr;
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.updateVoters(voters);
+		vm.stopPrank();
 
 		// Assert voteBalance and voter balance
 		validator = consensus.getValidator(addr);
@@ -73,6 +75,35 @@ contract ConsensusTest is Test {
 		assertEq(voterAddr.balance, 90 ether);
 	}
 
+	function test_get_vote() public {
+		address addr = address(1);
+		registerValidator(addr);
+
+		address voterAddr = address(2);
+		vm.deal(voterAddr, 100 ether);
+
+		// Not voted
+		Vote memory vote = consensus.getVote(voterAddr);
+		assertEq(vote.validator, address(0));
+		assertEq(vote.balance, 0);
+
+		vm.startPrank(voterAddr);
+		consensus.vote(addr);
+		vm.stopPrank();
+
+		vote = consensus.getVote(voterAddr);
+		assertEq(vote.validator, addr);
+		assertEq(vote.balance, 100 ether);
+
+		vm.startPrank(voterAddr);
+		consensus.unvote();
+		vm.stopPrank();
+
+		vote = consensus.getVote(voterAddr);
+		assertEq(vote.validator, address(0));
+		assertEq(vote.balance, 0);
+	}
+
 	function test_vote_revert_if_caller_is_owner() public {
 		vm.expectRevert("Caller is the contract owner");
 		consensus.vote(address(1));
@@ -107,7 +138,9 @@ contract ConsensusTest is Test {
 
 		address[] memory voters = new address[](1);
 		voters[0] = voterAddr;
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.updateVoters(voters);
+		vm.stopPrank();
 
 		// Assert voteBalance and voter balance
 		validator = consensus.getValidator(addr);
@@ -216,7 +249,9 @@ contract ConsensusTest is Test {
 		vm.deal(voterAddr, 90 ether);
 		address[] memory voters = new address[](1);
 		voters[0] = voterAddr;
+		vm.startPrank(SYSTEM_ADDRESS);
 		consensus.updateVoters(voters);
+		vm.stopPrank();
 
 		// Assert voteBalance and voter balance
 		validator = consensus.getValidator(addr);
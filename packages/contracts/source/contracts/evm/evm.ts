@@ -6,20 +6,35 @@ export enum EvmMode {
 }
 
 export interface GenesisInfo {
-	readonly account: string;
+	/** Account allocated the `initialSupply`, must not be listed in `alloc` */
+	readonly account?: string;
 	readonly deployerAccount: string;
 	readonly validatorContract: string;
-	readonly initialSupply: bigint;
+	readonly initialSupply?: bigint;
+	/** Accounts by address as in the `alloc` of a geth `genesis.json` */
+	readonly alloc?: Record<string, GenesisAccount>;
+}
+
+export interface GenesisAccount {
+	readonly balance?: string;
+	readonly nonce?: string;
+	readonly code?: string;
+	readonly storage?: Record<string, string>;
 }
 
 export interface Instance extends CommitHandler {
 	prepareNextCommit(context: PrepareNextCommitContext): Promise<void>;
 	process(txContext: TransactionContext): Promise<ProcessResult>;
 	view(viewContext: TransactionViewContext): Promise<ViewResult>;
-	initializeGenesis(commit: GenesisInfo): Promise<void>;
+	/** Prepares the genesis allocation for height 0 and returns its state hash */
+	initializeGenesis(commit: GenesisInfo): Promise<string>;
 	getAccountInfo(address: string): Promise<AccountInfo>;
-	calculateTopValidators(context: CalculateTopValidatorsContext): Promise<void>;
+	calculateTopValidators(context: CalculateTopValidatorsContext): Promise<Validator[]>;
 	updateRewardsAndVotes(context: UpdateRewardsAndVotesContext): Promise<void>;
+	getValidator(address: string, state?: StateSelector): Promise<Validator | null>;
+	getAllValidators(state?: StateSelector): Promise<Validator[]>;
+	getVoterOf(address: string, state?: StateSelector): Promise<Vote | null>;
+	getRegisteredValidatorsCount(state?: StateSelector): Promise<bigint>;
 	stateHash(commitKey: CommitKey, currentHash: string): Promise<string>;
 	codeAt(address: string): Promise<string>;
 	storageAt(address: string, slot: bigint): Promise<string>;
@@ -106,6 +121,24 @@ export interface UpdateRewardsAndVotesContext {
 	readonly specId: SpecId;
 }
 
+export interface Validator {
+	readonly address: string;
+	/** Hex encoded BLS12-381 public key */
+	readonly blsPublicKey: string;
+	readonly voteBalance: bigint;
+	readonly votersCount: bigint;
+	readonly isResigned: boolean;
+}
+
+export interface Vote {
+	/** Validator the vote is for */
+	readonly validator: string;
+	readonly balance: bigint;
+}
+
+/** Committed state only, or including the changes of the pending commit */
+export type StateSelector = "committed" | "pending";
+
 export interface CommitKey {
 	readonly height: bigint;
 	readonly round: bigint;
@@ -33,14 +33,16 @@ export class Deployer {
 
 		const validatorContractAddress = ethers.getCreateAddress({ from: this.#deployerAddress, nonce: 0 });
 
-		const genesisInfo = {
+		const genesisInfo: Contracts.Evm.GenesisInfo = {
 			account: genesisBlock.block.generatorAddress.slice(2),
 			deployerAccount: this.#deployerAddress,
 			initialSupply: Utils.BigNumber.make(genesisBlock.block.totalAmount).toBigInt(),
 			validatorContract: validatorContractAddress,
 		};
 
-		await this.evm.initializeGenesis(genesisInfo);
+		// The allocation becomes part of the commit at height 0, i.e. of the genesis block.
+		const genesisStateHash = await this.evm.initializeGenesis(genesisInfo);
+		this.logger.info(`Initialized genesis with state hash ${genesisStateHash}`);
 
 		const milestone = this.configuration.getMilestone(0);
 
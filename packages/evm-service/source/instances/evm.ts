@@ -26,9 +26,10 @@ export class EvmInstance implements Contracts.Evm.Instance {
 		return this.#evm.process(txContext);
 	}
 
-	public async initializeGenesis(commit: Contracts.Evm.GenesisInfo): Promise<void> {
+	public async initializeGenesis(commit: Contracts.Evm.GenesisInfo): Promise<string> {
 		return this.#evm.initializeGenesis({
 			account: commit.account,
+			alloc: commit.alloc,
 			deployerAccount: commit.deployerAccount,
 			initialSupply: commit.initialSupply,
 			validatorContract: commit.validatorContract,
@@ -43,10 +44,31 @@ export class EvmInstance implements Contracts.Evm.Instance {
 		return this.#evm.updateRewardsAndVotes(context);
 	}
 
-	public async calculateTopValidators(context: Contracts.Evm.CalculateTopValidatorsContext): Promise<void> {
+	public async calculateTopValidators(
+		context: Contracts.Evm.CalculateTopValidatorsContext,
+	): Promise<Contracts.Evm.Validator[]> {
 		return this.#evm.calculateTopValidators(context);
 	}
 
+	public async getValidator(
+		address: string,
+		state?: Contracts.Evm.StateSelector,
+	): Promise<Contracts.Evm.Validator | null> {
+		return this.#evm.getValidator(address, state);
+	}
+
+	public async getAllValidators(state?: Contracts.Evm.StateSelector): Promise<Contracts.Evm.Validator[]> {
+		return this.#evm.getAllValidators(state);
+	}
+
+	public async getVoterOf(address: string, state?: Contracts.Evm.StateSelector): Promise<Contracts.Evm.Vote | null> {
+		return this.#evm.getVoterOf(address, state);
+	}
+
+	public async getRegisteredValidatorsCount(state?: Contracts.Evm.StateSelector): Promise<bigint> {
+		return this.#evm.getRegisteredValidatorsCount(state);
+	}
+
 	public async onCommit(unit: Contracts.Processor.ProcessableUnit): Promise<void> {
 		const { height } = unit;
 		const round = unit.getBlock().data.round;
@@ -1,9 +1,15 @@
-use std::str::FromStr;
-
-use mainsail_evm_core::db::CommitKey;
+use std::{collections::BTreeMap, str::FromStr};
+
+use mainsail_evm_core::{
+    db::{CommitKey, MapSizeGrowth, PersistentDBOptions, SyncMode},
+    execution::Transaction,
+    precompiles::{consensus::ConsensusData, PrecompileRegistry},
+    pruning::PruningMode,
+    state_commit::{FeeDistribution, GenesisAccount, Penalty, PenaltyMode, Reward},
+};
 use napi::{JsBigInt, JsBuffer, JsString};
 use napi_derive::napi;
-use revm::primitives::{Address, Bytes, SpecId, B256, U256};
+use revm::primitives::{Address, BlockEnv, Bytes, SpecId, TransactTo, TxEnv, B256, U256};
 
 use crate::utils;
 
@@ -22,6 +28,23 @@ pub struct JsTransactionContext {
     pub spec_id: JsString,
 }
 
+#[napi(object)]
+pub struct JsValidateTransactionContext {
+    pub caller: JsString,
+    /// Omit recipient when deploying a contract
+    pub recipient: Option<JsString>,
+    pub gas_limit: JsBigInt,
+    pub gas_price: Option<JsBigInt>,
+    pub value: JsBigInt,
+    pub nonce: JsBigInt,
+    pub data: JsBuffer,
+    pub spec_id: JsString,
+    /// Next nonce of the sender including transactions that are already in the pool
+    pub pending_nonce: Option<JsBigInt>,
+    /// Reject transactions that do not fit into a block with this gas limit
+    pub block_gas_limit: Option<JsBigInt>,
+}
+
 #[napi(object)]
 pub struct JsTransactionViewContext {
     pub caller: JsString,
@@ -36,14 +59,46 @@ pub struct JsBlockContext {
     pub gas_limit: JsBigInt,
     pub timestamp: JsBigInt,
     pub validator_address: JsString,
+    /// Active validator set exposed by the consensus data precompile
+    pub active_validators: Option<Vec<JsString>>,
+    /// 32 byte randomness derived by consensus, exposed as `block.prevrandao`
+    pub randomness: Option<JsString>,
+    /// Distribute the fees of the commit instead of paying them to the validator per transaction
+    pub fee_distribution: Option<JsFeeDistribution>,
+}
+
+#[napi(object)]
+pub struct JsFeeDistribution {
+    /// Share of the fees that is burned, in basis points
+    pub burn_bps: u32,
+    /// Share of the fees credited to `treasury`, in basis points. The validator receives the rest
+    pub treasury_bps: u32,
+    pub treasury: JsString,
+}
+
+#[napi(object)]
+pub struct JsProcessBatchContext {
+    pub block_context: JsBlockContext,
+    pub spec_id: JsString,
+    /// Binary encoded transactions, see `ProcessBatchContext::decode_transactions`
+    pub transactions: JsBuffer,
+    /// Reward the validator and update votes after all transactions are processed
+    pub block_reward: Option<JsBigInt>,
+    /// Rewards credited together with the block reward
+    pub rewards: Option<Vec<JsReward>>,
+    /// Calculate the state hash on top of this hash after all transactions are processed
+    pub current_hash: Option<JsString>,
 }
 
 #[napi(object)]
 pub struct JsGenesisContext {
-    pub account: JsString,
+    /// Allocated `initial_supply`, must not be listed in `alloc`
+    pub account: Option<JsString>,
     pub deployer_account: JsString,
     pub validator_contract: JsString,
-    pub initial_supply: JsBigInt,
+    pub initial_supply: Option<JsBigInt>,
+    /// Accounts by address as in the `alloc` of a geth `genesis.json`
+    pub alloc: Option<serde_json::Value>,
 }
 
 #[napi(object)]
@@ -62,6 +117,33 @@ pub struct JsUpdateRewardsAndVotesContext {
     pub block_reward: JsBigInt,
     pub validator_address: JsString,
     pub spec_id: JsString,
+    /// Rewards credited together with the block reward of the validator, e.g. voter shares
+    pub rewards: Option<Vec<JsReward>>,
+}
+
+#[napi(object)]
+pub struct JsReward {
+    pub address: JsString,
+    pub amount: JsBigInt,
+    pub kind: JsString,
+}
+
+#[napi(object)]
+pub struct JsApplyPenaltiesContext {
+    pub commit_key: JsCommitKey,
+    pub timestamp: JsBigInt,
+    pub validator_address: JsString,
+    pub spec_id: JsString,
+    pub penalties: Vec<JsPenalty>,
+    /// Reject all penalties if any exceeds the balance left instead of deducting what is left
+    pub strict: Option<bool>,
+}
+
+#[napi(object)]
+pub struct JsPenalty {
+    pub address: JsString,
+    pub amount: JsBigInt,
+    pub reason: JsString,
 }
 
 #[napi(object)]
@@ -70,17 +152,65 @@ pub struct JsCommitKey {
     pub round: JsBigInt,
 }
 
+#[napi(object)]
+pub struct JsEvmOptions {
+    pub initial_map_size: Option<JsBigInt>,
+    /// Grow the map size in steps of this many bytes (default 1 GB)
+    pub map_size_growth_step: Option<JsBigInt>,
+    /// Grow the map size by this factor, takes precedence over the growth step
+    pub map_size_growth_factor: Option<f64>,
+    pub max_map_size: Option<JsBigInt>,
+    /// One of "full" (default), "noMetaSync" or "noSync"
+    pub sync_mode: Option<JsString>,
+    pub read_ahead: Option<bool>,
+    /// One of "archive" (default), "keepLast" or "hashesOnly"
+    pub pruning_mode: Option<JsString>,
+    /// Number of heights to keep receipts for when pruning with "keepLast"
+    pub pruning_keep_last: Option<JsBigInt>,
+    /// Height from which the BLS12-381 precompiles (EIP-2537) are available
+    pub bls12_381_activation_height: Option<JsBigInt>,
+    /// Height from which the BIP-340 Schnorr verification precompile (0x0a01) is available
+    pub schnorr_activation_height: Option<JsBigInt>,
+    /// Height from which the Ed25519 verification precompile (0x0a02) is available
+    pub ed25519_activation_height: Option<JsBigInt>,
+    /// Height from which the consensus data precompile (0x0a03) is available
+    pub consensus_data_activation_height: Option<JsBigInt>,
+    /// Execute the transactions of a batch optimistically in parallel (default false)
+    pub parallel_execution: Option<bool>,
+}
+
+#[derive(Debug, Default)]
+pub struct EvmOptions {
+    pub db: PersistentDBOptions,
+    pub precompiles: PrecompileRegistry,
+    /// See `mainsail_evm_core::execution::execute_parallel`
+    pub parallel_execution: bool,
+}
+
 #[napi(object)]
 pub struct JsPrepareNextCommitContext {
     pub commit_key: JsCommitKey,
+    /// 32 byte randomness derived by consensus, exposed as `block.prevrandao`
+    pub randomness: Option<JsString>,
+}
+
+/// Selects the state a read reports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum StateSelector {
+    /// State as of the last commit
+    #[default]
+    Committed,
+    /// Committed state including the changes of the pending commit
+    Pending,
 }
 
 #[derive(Debug)]
 pub struct PrepareNextCommitContext {
     pub commit_key: CommitKey,
+    pub randomness: Option<B256>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct TxContext {
     pub caller: Address,
     /// Omit recipient when deploying a contract
@@ -95,6 +225,20 @@ pub struct TxContext {
     pub spec_id: SpecId,
 }
 
+#[derive(Debug)]
+pub struct ValidateTransactionContext {
+    pub caller: Address,
+    pub recipient: Option<Address>,
+    pub gas_limit: u64,
+    pub gas_price: Option<U256>,
+    pub value: U256,
+    pub nonce: u64,
+    pub data: Bytes,
+    pub spec_id: SpecId,
+    pub pending_nonce: Option<u64>,
+    pub block_gas_limit: Option<u64>,
+}
+
 #[derive(Debug)]
 pub struct TxViewContext {
     pub caller: Address,
@@ -103,20 +247,33 @@ pub struct TxViewContext {
     pub spec_id: SpecId,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone, Default)]
 pub struct BlockContext {
     pub commit_key: CommitKey,
     pub gas_limit: U256,
     pub timestamp: U256,
     pub validator_address: Address,
+    pub active_validators: Vec<Address>,
+    /// Replaces the randomness of the pending commit if set
+    pub randomness: Option<B256>,
+    pub fee_distribution: Option<FeeDistribution>,
+}
+
+#[derive(Debug)]
+pub struct ProcessBatchContext {
+    pub block_context: BlockContext,
+    pub spec_id: SpecId,
+    pub transactions: Vec<u8>,
+    pub block_reward: Option<u128>,
+    pub rewards: Vec<Reward>,
+    pub current_hash: Option<B256>,
 }
 
 #[derive(Debug)]
 pub struct GenesisContext {
-    pub account: Address,
     pub deployer_account: Address,
     pub validator_contract: Address,
-    pub initial_supply: U256,
+    pub alloc: BTreeMap<Address, GenesisAccount>,
 }
 
 #[derive(Debug)]
@@ -135,52 +292,73 @@ pub struct UpdateRewardsAndVotesContext {
     pub block_reward: u128,
     pub validator_address: Address,
     pub spec_id: SpecId,
+    pub rewards: Vec<Reward>,
 }
 
 #[derive(Debug)]
-pub struct ExecutionContext {
-    pub caller: Address,
-    pub recipient: Option<Address>,
-    pub gas_limit: Option<u64>,
-    pub gas_price: Option<U256>,
-    pub value: U256,
-    pub nonce: Option<u64>,
-    pub data: Bytes,
-    pub tx_hash: Option<B256>,
-    pub block_context: Option<BlockContext>,
+pub struct ApplyPenaltiesContext {
+    pub commit_key: CommitKey,
+    pub timestamp: U256,
+    pub validator_address: Address,
     pub spec_id: SpecId,
+    pub penalties: Vec<Penalty>,
+    pub mode: PenaltyMode,
+}
+
+// Gas available to calls outside of a block, which do not specify a limit
+const VIEW_GAS_LIMIT: u64 = 15_000_000;
+
+impl TxViewContext {
+    pub fn tx_env(&self) -> TxEnv {
+        TxEnv {
+            caller: self.caller,
+            gas_limit: VIEW_GAS_LIMIT,
+            transact_to: TransactTo::Call(self.recipient),
+            data: self.data.clone(),
+            ..Default::default()
+        }
+    }
 }
 
-impl From<TxViewContext> for ExecutionContext {
-    fn from(value: TxViewContext) -> Self {
-        Self {
-            caller: value.caller,
-            recipient: Some(value.recipient),
-            gas_limit: None,
-            gas_price: None,
-            value: U256::ZERO,
-            nonce: None,
-            data: value.data,
-            tx_hash: None,
-            block_context: None,
-            spec_id: value.spec_id,
+impl TxContext {
+    pub fn transaction(&self) -> Transaction {
+        Transaction {
+            tx_hash: Some(self.tx_hash),
+            env: TxEnv {
+                caller: self.caller,
+                gas_limit: self.gas_limit,
+                gas_price: self.gas_price.unwrap_or_default(),
+                transact_to: match self.recipient {
+                    Some(recipient) => TransactTo::Call(recipient),
+                    None => TransactTo::Create,
+                },
+                value: self.value,
+                data: self.data.clone(),
+                nonce: Some(self.nonce),
+                ..Default::default()
+            },
         }
     }
 }
 
-impl From<TxContext> for ExecutionContext {
-    fn from(value: TxContext) -> Self {
-        Self {
-            caller: value.caller,
-            recipient: value.recipient,
-            gas_limit: Some(value.gas_limit),
-            gas_price: value.gas_price,
-            value: value.value,
-            nonce: Some(value.nonce),
-            data: value.data,
-            tx_hash: Some(value.tx_hash),
-            block_context: Some(value.block_context),
-            spec_id: value.spec_id,
+impl BlockContext {
+    pub fn block_env(&self, randomness: B256) -> BlockEnv {
+        BlockEnv {
+            number: U256::from(self.commit_key.0),
+            coinbase: self.validator_address,
+            timestamp: self.timestamp,
+            gas_limit: self.gas_limit,
+            difficulty: U256::ZERO,
+            prevrandao: Some(randomness),
+            ..Default::default()
+        }
+    }
+
+    pub fn consensus_data(&self) -> ConsensusData {
+        ConsensusData {
+            round: self.commit_key.1,
+            proposer: self.validator_address,
+            validators: self.active_validators.clone(),
         }
     }
 }
@@ -196,12 +374,94 @@ impl TryFrom<JsCommitKey> for CommitKey {
     }
 }
 
+impl TryFrom<JsEvmOptions> for EvmOptions {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsEvmOptions) -> Result<Self, Self::Error> {
+        let mut options = PersistentDBOptions::default();
+
+        if let Some(initial_map_size) = value.initial_map_size {
+            options.initial_map_size = usize::try_from(initial_map_size.get_u64()?.0)?;
+        }
+
+        if let Some(step) = value.map_size_growth_step {
+            options.map_size_growth = MapSizeGrowth::Step(usize::try_from(step.get_u64()?.0)?);
+        }
+
+        if let Some(factor) = value.map_size_growth_factor {
+            if factor <= 1.0 {
+                return Err(anyhow::anyhow!("map size growth factor must be above 1"));
+            }
+
+            options.map_size_growth = MapSizeGrowth::Factor(factor);
+        }
+
+        if let Some(max_map_size) = value.max_map_size {
+            options.max_map_size = Some(usize::try_from(max_map_size.get_u64()?.0)?);
+        }
+
+        if let Some(sync_mode) = value.sync_mode {
+            options.sync_mode = match sync_mode.into_utf8()?.as_str()? {
+                "full" => SyncMode::Full,
+                "noMetaSync" => SyncMode::NoMetaSync,
+                "noSync" => SyncMode::NoSync,
+                _ => return Err(anyhow::anyhow!("invalid sync_mode")),
+            };
+        }
+
+        if let Some(read_ahead) = value.read_ahead {
+            options.read_ahead = read_ahead;
+        }
+
+        if let Some(pruning_mode) = value.pruning_mode {
+            options.pruning_mode = match pruning_mode.into_utf8()?.as_str()? {
+                "archive" => PruningMode::Archive,
+                "keepLast" => match value.pruning_keep_last {
+                    Some(keep_last) => PruningMode::KeepLast(keep_last.get_u64()?.0),
+                    None => return Err(anyhow::anyhow!("missing pruning_keep_last")),
+                },
+                "hashesOnly" => PruningMode::HashesOnly,
+                _ => return Err(anyhow::anyhow!("invalid pruning_mode")),
+            };
+        }
+
+        let mut precompiles = PrecompileRegistry::default();
+        if let Some(height) = value.bls12_381_activation_height {
+            precompiles.register_bls12_381(height.get_u64()?.0);
+        }
+
+        let schnorr_height = match value.schnorr_activation_height {
+            Some(height) => Some(height.get_u64()?.0),
+            None => None,
+        };
+        let ed25519_height = match value.ed25519_activation_height {
+            Some(height) => Some(height.get_u64()?.0),
+            None => None,
+        };
+        precompiles.register_signatures(schnorr_height, ed25519_height);
+
+        if let Some(height) = value.consensus_data_activation_height {
+            precompiles.register_consensus_data(height.get_u64()?.0);
+        }
+
+        Ok(EvmOptions {
+            db: options,
+            precompiles,
+            parallel_execution: value.parallel_execution.unwrap_or_default(),
+        })
+    }
+}
+
 impl TryFrom<JsPrepareNextCommitContext> for PrepareNextCommitContext {
     type Error = anyhow::Error;
 
     fn try_from(value: JsPrepareNextCommitContext) -> Result<Self, Self::Error> {
         Ok(PrepareNextCommitContext {
             commit_key: value.commit_key.try_into()?,
+            randomness: match value.randomness {
+                Some(randomness) => Some(utils::convert_string_to_b256(randomness)?),
+                None => None,
+            },
         })
     }
 }
@@ -215,10 +475,37 @@ impl TryFrom<JsBlockContext> for BlockContext {
             gas_limit: U256::from(value.gas_limit.get_u64()?.0),
             timestamp: U256::from(value.timestamp.get_u64()?.0),
             validator_address: utils::create_address_from_js_string(value.validator_address)?,
+            active_validators: value
+                .active_validators
+                .unwrap_or_default()
+                .into_iter()
+                .map(utils::create_address_from_js_string)
+                .collect::<Result<_, _>>()?,
+            randomness: match value.randomness {
+                Some(randomness) => Some(utils::convert_string_to_b256(randomness)?),
+                None => None,
+            },
+            fee_distribution: match value.fee_distribution {
+                Some(fee_distribution) => Some(fee_distribution.try_into()?),
+                None => None,
+            },
         })
     }
 }
 
+impl TryFrom<JsFeeDistribution> for FeeDistribution {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsFeeDistribution) -> Result<Self, Self::Error> {
+        FeeDistribution::new(
+            value.burn_bps.try_into()?,
+            value.treasury_bps.try_into()?,
+            utils::create_address_from_js_string(value.treasury)?,
+        )
+        .ok_or_else(|| anyhow::anyhow!("fee distribution shares exceed 10000 basis points"))
+    }
+}
+
 impl TryFrom<JsTransactionContext> for TxContext {
     type Error = anyhow::Error;
 
@@ -256,6 +543,47 @@ impl TryFrom<JsTransactionContext> for TxContext {
     }
 }
 
+impl TryFrom<JsValidateTransactionContext> for ValidateTransactionContext {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsValidateTransactionContext) -> std::result::Result<Self, Self::Error> {
+        let buf = value.data.into_value()?;
+
+        let recipient = match value.recipient {
+            Some(recipient) => Some(utils::create_address_from_js_string(recipient)?),
+            None => None,
+        };
+
+        let gas_price = match value.gas_price {
+            Some(gas_price) => Some(utils::convert_bigint_to_u256(gas_price)?),
+            None => None,
+        };
+
+        let pending_nonce = match value.pending_nonce {
+            Some(pending_nonce) => Some(pending_nonce.get_u64()?.0),
+            None => None,
+        };
+
+        let block_gas_limit = match value.block_gas_limit {
+            Some(block_gas_limit) => Some(block_gas_limit.get_u64()?.0),
+            None => None,
+        };
+
+        Ok(ValidateTransactionContext {
+            caller: utils::create_address_from_js_string(value.caller)?,
+            recipient,
+            gas_limit: value.gas_limit.try_into()?,
+            gas_price,
+            value: utils::convert_bigint_to_u256(value.value)?,
+            nonce: value.nonce.get_u64()?.0,
+            data: Bytes::from(buf.as_ref().to_owned()),
+            spec_id: parse_spec_id(value.spec_id)?,
+            pending_nonce,
+            block_gas_limit,
+        })
+    }
+}
+
 impl TryFrom<JsTransactionViewContext> for TxViewContext {
     type Error = anyhow::Error;
 
@@ -273,15 +601,133 @@ impl TryFrom<JsTransactionViewContext> for TxViewContext {
     }
 }
 
+impl TryFrom<JsProcessBatchContext> for ProcessBatchContext {
+    type Error = anyhow::Error;
+
+    fn try_from(mut value: JsProcessBatchContext) -> Result<Self, Self::Error> {
+        let block_reward = match value.block_reward.as_mut() {
+            Some(block_reward) => Some(block_reward.get_u128()?.1),
+            None => None,
+        };
+
+        let current_hash = match value.current_hash {
+            Some(current_hash) => Some(utils::convert_string_to_b256(current_hash)?),
+            None => None,
+        };
+
+        Ok(ProcessBatchContext {
+            block_context: value.block_context.try_into()?,
+            spec_id: parse_spec_id(value.spec_id)?,
+            transactions: value.transactions.into_value()?.to_vec(),
+            block_reward,
+            rewards: parse_rewards(value.rewards)?,
+            current_hash,
+        })
+    }
+}
+
+// caller | has recipient | recipient | gas limit | has gas price | gas price | value | nonce | tx hash
+const BATCH_TX_HEADER_SIZE: usize = 20 + 1 + 20 + 8 + 1 + 32 + 32 + 8 + 32;
+
+impl ProcessBatchContext {
+    /// Decodes the transactions of a batch. Each transaction is prefixed with its length as
+    /// big-endian u32 and laid out as follows (integers big-endian):
+    ///
+    /// caller (20) | has recipient (1) | recipient (20) | gas limit (8) | has gas price (1) |
+    /// gas price (32) | value (32) | nonce (8) | tx hash (32) | data (remaining bytes)
+    ///
+    /// Absent recipient and gas price are encoded as zero bytes.
+    pub fn decode_transactions(&self) -> anyhow::Result<Vec<TxContext>> {
+        let mut transactions = Vec::new();
+        let mut buf = self.transactions.as_slice();
+
+        while !buf.is_empty() {
+            let (len, rest) = split_checked(buf, 4)?;
+            let len = u32::from_be_bytes(len.try_into()?) as usize;
+            let (tx, rest) = split_checked(rest, len)?;
+            buf = rest;
+
+            if tx.len() < BATCH_TX_HEADER_SIZE {
+                return Err(anyhow::anyhow!(
+                    "batch transaction {} too short",
+                    transactions.len()
+                ));
+            }
+
+            let (caller, tx) = tx.split_at(20);
+            let (has_recipient, tx) = tx.split_at(1);
+            let (recipient, tx) = tx.split_at(20);
+            let (gas_limit, tx) = tx.split_at(8);
+            let (has_gas_price, tx) = tx.split_at(1);
+            let (gas_price, tx) = tx.split_at(32);
+            let (value, tx) = tx.split_at(32);
+            let (nonce, tx) = tx.split_at(8);
+            let (tx_hash, data) = tx.split_at(32);
+
+            transactions.push(TxContext {
+                caller: Address::from_slice(caller),
+                recipient: (has_recipient[0] != 0).then(|| Address::from_slice(recipient)),
+                gas_limit: u64::from_be_bytes(gas_limit.try_into()?),
+                gas_price: (has_gas_price[0] != 0).then(|| U256::from_be_slice(gas_price)),
+                value: U256::from_be_slice(value),
+                nonce: u64::from_be_bytes(nonce.try_into()?),
+                data: Bytes::copy_from_slice(data),
+                tx_hash: B256::from_slice(tx_hash),
+                block_context: self.block_context.clone(),
+                spec_id: self.spec_id,
+            });
+        }
+
+        Ok(transactions)
+    }
+}
+
+fn split_checked(buf: &[u8], mid: usize) -> anyhow::Result<(&[u8], &[u8])> {
+    if buf.len() < mid {
+        return Err(anyhow::anyhow!("unexpected end of batch"));
+    }
+
+    Ok(buf.split_at(mid))
+}
+
 impl TryFrom<JsGenesisContext> for GenesisContext {
     type Error = anyhow::Error;
 
     fn try_from(value: JsGenesisContext) -> Result<Self, Self::Error> {
+        let mut alloc: BTreeMap<Address, GenesisAccount> = match value.alloc {
+            Some(alloc) => serde_json::from_value(alloc)?,
+            None => Default::default(),
+        };
+
+        match (value.account, value.initial_supply) {
+            (Some(account), Some(initial_supply)) => {
+                let account = utils::create_address_from_js_string(account)?;
+                if alloc.contains_key(&account) {
+                    return Err(anyhow::anyhow!(
+                        "genesis account {account} is also listed in alloc"
+                    ));
+                }
+
+                alloc.insert(
+                    account,
+                    GenesisAccount {
+                        balance: utils::convert_bigint_to_u256(initial_supply)?,
+                        ..Default::default()
+                    },
+                );
+            }
+            (None, None) => (),
+            _ => {
+                return Err(anyhow::anyhow!(
+                    "genesis account and initial supply must be given together"
+                ))
+            }
+        }
+
         Ok(GenesisContext {
-            account: utils::create_address_from_js_string(value.account)?,
             validator_contract: utils::create_address_from_js_string(value.validator_contract)?,
             deployer_account: utils::create_address_from_js_string(value.deployer_account)?,
-            initial_supply: utils::convert_bigint_to_u256(value.initial_supply)?,
+            alloc,
         })
     }
 }
@@ -313,17 +759,90 @@ impl TryFrom<JsUpdateRewardsAndVotesContext> for UpdateRewardsAndVotesContext {
             validator_address: utils::create_address_from_js_string(value.validator_address)?,
             block_reward: value.block_reward.get_u128()?.1,
             spec_id: parse_spec_id(value.spec_id)?,
+            rewards: parse_rewards(value.rewards)?,
+        })
+    }
+}
+
+impl TryFrom<JsReward> for Reward {
+    type Error = anyhow::Error;
+
+    fn try_from(mut value: JsReward) -> Result<Self, Self::Error> {
+        let (negative, amount, lossless) = value.amount.get_u128()?;
+        anyhow::ensure!(!negative && lossless, "invalid reward amount");
+
+        Ok(Reward {
+            address: utils::create_address_from_js_string(value.address)?,
+            amount,
+            kind: value.kind.into_utf8()?.into_owned()?,
+        })
+    }
+}
+
+impl TryFrom<JsApplyPenaltiesContext> for ApplyPenaltiesContext {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsApplyPenaltiesContext) -> Result<Self, Self::Error> {
+        Ok(ApplyPenaltiesContext {
+            commit_key: value.commit_key.try_into()?,
+            timestamp: U256::from(value.timestamp.get_u64()?.0),
+            validator_address: utils::create_address_from_js_string(value.validator_address)?,
+            spec_id: parse_spec_id(value.spec_id)?,
+            penalties: value
+                .penalties
+                .into_iter()
+                .map(Penalty::try_from)
+                .collect::<Result<_, _>>()?,
+            mode: match value.strict {
+                Some(true) => PenaltyMode::Strict,
+                _ => PenaltyMode::Saturating,
+            },
+        })
+    }
+}
+
+impl TryFrom<JsPenalty> for Penalty {
+    type Error = anyhow::Error;
+
+    fn try_from(value: JsPenalty) -> Result<Self, Self::Error> {
+        Ok(Penalty {
+            address: utils::create_address_from_js_string(value.address)?,
+            amount: utils::convert_bigint_to_u256(value.amount)?,
+            reason: value.reason.into_utf8()?.into_owned()?,
         })
     }
 }
 
+fn parse_rewards(rewards: Option<Vec<JsReward>>) -> Result<Vec<Reward>, anyhow::Error> {
+    rewards
+        .unwrap_or_default()
+        .into_iter()
+        .map(Reward::try_from)
+        .collect()
+}
+
+/// Parses "committed" (default) or "pending".
+pub fn parse_state_selector(state: Option<JsString>) -> Result<StateSelector, anyhow::Error> {
+    let Some(state) = state else {
+        return Ok(StateSelector::default());
+    };
+
+    match state.into_utf8()?.as_str()? {
+        "committed" => Ok(StateSelector::Committed),
+        "pending" => Ok(StateSelector::Pending),
+        _ => Err(anyhow::anyhow!("invalid state selector")),
+    }
+}
+
+// By default "Latest" also includes unreleased specs, hence pin it to a specific spec which we
+// can change manually as needed.
+pub const LATEST_SPEC_ID: SpecId = SpecId::SHANGHAI;
+
 fn parse_spec_id(spec_id: JsString) -> Result<SpecId, anyhow::Error> {
     let spec_id = spec_id.into_utf8()?.into_owned()?;
 
-    // By default "Latest" also includes unreleased specs, hence pin it to a specific spec which we
-    // can change manually as needed.
     if spec_id == "Latest" {
-        return Ok(SpecId::SHANGHAI);
+        return Ok(LATEST_SPEC_ID);
     }
 
     // Any supported spec is listed in the first match arm
@@ -333,3 +852,119 @@ fn parse_spec_id(spec_id: JsString) -> Result<SpecId, anyhow::Error> {
         _ => Err(anyhow::anyhow!("invalid spec_id")),
     }
 }
+
+#[cfg(test)]
+fn encode_batch_tx(tx: &TxContext) -> Vec<u8> {
+    let mut buf = Vec::new();
+    buf.extend_from_slice(tx.caller.as_slice());
+    buf.push(tx.recipient.is_some() as u8);
+    buf.extend_from_slice(tx.recipient.unwrap_or_default().as_slice());
+    buf.extend_from_slice(&tx.gas_limit.to_be_bytes());
+    buf.push(tx.gas_price.is_some() as u8);
+    buf.extend_from_slice(&tx.gas_price.unwrap_or_default().to_be_bytes::<32>());
+    buf.extend_from_slice(&tx.value.to_be_bytes::<32>());
+    buf.extend_from_slice(&tx.nonce.to_be_bytes());
+    buf.extend_from_slice(tx.tx_hash.as_slice());
+    buf.extend_from_slice(&tx.data);
+
+    let mut encoded = (buf.len() as u32).to_be_bytes().to_vec();
+    encoded.extend(buf);
+    encoded
+}
+
+#[cfg(test)]
+pub(crate) fn encode_batch(transactions: &[TxContext]) -> Vec<u8> {
+    transactions.iter().flat_map(encode_batch_tx).collect()
+}
+
+#[test]
+fn test_decode_transactions() {
+    use revm::primitives::{address, b256, bytes};
+
+    let batch = |transactions: Vec<u8>| ProcessBatchContext {
+        block_context: Default::default(),
+        spec_id: SpecId::SHANGHAI,
+        transactions,
+        block_reward: None,
+        rewards: Default::default(),
+        current_hash: None,
+    };
+
+    let transfer = TxContext {
+        caller: address!("00000000000000000000000000000000000000a1"),
+        recipient: Some(address!("00000000000000000000000000000000000000b1")),
+        gas_limit: 21_000,
+        gas_price: Some(U256::from(5)),
+        value: U256::from(100),
+        nonce: 1,
+        data: Bytes::new(),
+        tx_hash: b256!("0000000000000000000000000000000000000000000000000000000000000001"),
+        block_context: Default::default(),
+        spec_id: SpecId::SHANGHAI,
+    };
+    let deploy = TxContext {
+        recipient: None,
+        gas_price: None,
+        data: bytes!("6080604052"),
+        tx_hash: b256!("0000000000000000000000000000000000000000000000000000000000000002"),
+        ..transfer.clone()
+    };
+
+    let encoded = encode_batch(&[transfer.clone(), deploy.clone()]);
+    let decoded = batch(encoded.clone())
+        .decode_transactions()
+        .expect("decode");
+    assert_eq!(decoded.len(), 2);
+    for (decoded, expected) in decoded.iter().zip([&transfer, &deploy]) {
+        assert_eq!(
+            (
+                decoded.caller,
+                decoded.recipient,
+                decoded.gas_limit,
+                decoded.gas_price,
+                decoded.value,
+                decoded.nonce,
+                &decoded.data,
+                decoded.tx_hash
+            ),
+            (
+                expected.caller,
+                expected.recipient,
+                expected.gas_limit,
+                expected.gas_price,
+                expected.value,
+                expected.nonce,
+                &expected.data,
+                expected.tx_hash
+            )
+        );
+    }
+
+    // empty batch
+    assert!(batch(vec![])
+        .decode_transactions()
+        .expect("decode")
+        .is_empty());
+
+    // a transaction without data is exactly the header
+    let header = &encode_batch(std::slice::from_ref(&transfer))[4..];
+    assert_eq!(header.len(), BATCH_TX_HEADER_SIZE);
+
+    // one byte short of the header
+    let mut short = ((BATCH_TX_HEADER_SIZE - 1) as u32).to_be_bytes().to_vec();
+    short.extend_from_slice(&header[..BATCH_TX_HEADER_SIZE - 1]);
+    assert!(batch(short).decode_transactions().is_err());
+
+    // truncated length prefix
+    assert!(batch(vec![0, 0, 1]).decode_transactions().is_err());
+
+    // truncated transaction of the second entry
+    assert!(batch(encoded[..encoded.len() - 1].to_vec())
+        .decode_transactions()
+        .is_err());
+
+    // length beyond the end of the batch
+    let mut overflow = u32::MAX.to_be_bytes().to_vec();
+    overflow.extend_from_slice(header);
+    assert!(batch(overflow).decode_transactions().is_err());
+}
@@ -1,27 +1,34 @@
-use std::{collections::HashMap, path::PathBuf, sync::Arc, u64};
+use std::{path::PathBuf, sync::Arc};
 
+use alloy_sol_types::SolCall;
 use ctx::{
-    BlockContext, CalculateTopValidatorsContext, ExecutionContext, GenesisContext,
-    JsCalculateTopValidatorsContext, JsCommitKey, JsGenesisContext, JsPrepareNextCommitContext,
-    JsTransactionContext, JsTransactionViewContext, JsUpdateRewardsAndVotesContext,
-    PrepareNextCommitContext, TxContext, TxViewContext, UpdateRewardsAndVotesContext,
+    ApplyPenaltiesContext, BlockContext, CalculateTopValidatorsContext, EvmOptions, GenesisContext,
+    JsApplyPenaltiesContext, JsCalculateTopValidatorsContext, JsCommitKey, JsEvmOptions,
+    JsGenesisContext, JsPrepareNextCommitContext, JsProcessBatchContext, JsTransactionContext,
+    JsTransactionViewContext, JsUpdateRewardsAndVotesContext, JsValidateTransactionContext,
+    PrepareNextCommitContext, ProcessBatchContext, StateSelector, TxContext, TxViewContext,
+    UpdateRewardsAndVotesContext, ValidateTransactionContext,
 };
 use mainsail_evm_core::{
-    db::{CommitKey, GenesisInfo, PendingCommit, PersistentDB},
-    receipt::{map_execution_result, TxReceipt},
-    state_changes::AccountUpdate,
-    state_commit, state_hash,
+    db::{CommitKey, GenesisInfo, PendingCommit, PendingStateRef, PersistentDB, ValidatorSet},
+    execution,
+    precompiles::PrecompileRegistry,
+    receipt::{map_execution_result, map_system_results, TxReceipt},
+    state_commit::{self, Reward},
+    state_hash,
+    system_call::{Consensus, SystemCallBlock, SystemCallOutput, SystemCaller, Validator, Vote},
+    validation::{self, TxValidation},
+    voters,
 };
 use napi::{bindgen_prelude::*, JsBigInt, JsObject, JsString};
 use napi_derive::napi;
-use result::{CommitResult, TxViewResult};
+use result::{CommitResult, ProcessBatchResult, StatsResult, TxViewResult};
 use revm::{
-    db::{State, WrapDatabaseRef},
     primitives::{
-        hex::ToHexExt, AccountInfo, Address, Bytecode, Bytes, EVMError, ExecutionResult,
-        ResultAndState, B256, U256,
+        hex::ToHexExt, AccountInfo, Address, BlockEnv, Bytecode, Bytes, EVMError, ExecutionResult,
+        SpecId, TxEnv, B256, U256,
     },
-    Database, DatabaseCommit, Evm, TransitionAccount,
+    DatabaseRef,
 };
 
 mod ctx;
@@ -34,18 +41,29 @@ pub struct EvmInner {
 
     // A pending commit consists of one or more transactions.
     pending_commit: Option<PendingCommit>,
+
+    // The genesis allocation, seeds the pending commit of height 0 until it is committed.
+    genesis_commit: Option<PendingCommit>,
+
+    precompiles: PrecompileRegistry,
+
+    // Execute the transactions of a batch in parallel
+    parallel_execution: bool,
 }
 
 // NOTE: we guarantee that this can be sent between threads, since it only is accessed through a mutex
 unsafe impl Send for EvmInner {}
 
 impl EvmInner {
-    pub fn new(path: PathBuf) -> Self {
-        let persistent_db = PersistentDB::new(path).expect("path ok");
+    pub fn new(path: PathBuf, options: EvmOptions) -> Self {
+        let persistent_db = PersistentDB::new_with_options(path, options.db).expect("path ok");
 
         EvmInner {
             persistent_db,
             pending_commit: Default::default(),
+            genesis_commit: Default::default(),
+            precompiles: options.precompiles,
+            parallel_execution: options.parallel_execution,
         }
     }
 
@@ -58,15 +76,15 @@ impl EvmInner {
         }
 
         self.pending_commit.replace(PendingCommit {
-            key: ctx.commit_key,
-            ..Default::default()
+            randomness: ctx.randomness.unwrap_or_default(),
+            ..self.new_pending_commit(ctx.commit_key)
         });
 
         Ok(())
     }
 
     pub fn view(&mut self, tx_ctx: TxViewContext) -> Result<TxViewResult> {
-        let result = self.transact_evm(tx_ctx.into());
+        let result = self.execute_view(&tx_ctx);
 
         Ok(match result {
             Ok(r) => {
@@ -86,20 +104,58 @@ impl EvmInner {
         })
     }
 
-    pub fn code_at(&mut self, address: Address) -> std::result::Result<Bytes, EVMError<String>> {
-        let account = self
-            .persistent_db
-            .basic(address)
+    pub fn validate_transaction(
+        &mut self,
+        ctx: ValidateTransactionContext,
+    ) -> std::result::Result<TxValidation, EVMError<String>> {
+        let mut block_env = BlockEnv::default();
+        if let Some(block_gas_limit) = ctx.block_gas_limit {
+            block_env.gas_limit = U256::from(block_gas_limit);
+        }
+
+        let tx_env = TxEnv {
+            caller: ctx.caller,
+            gas_limit: ctx.gas_limit,
+            gas_price: ctx.gas_price.unwrap_or_default(),
+            transact_to: match ctx.recipient {
+                Some(recipient) => revm::primitives::TransactTo::Call(recipient),
+                None => revm::primitives::TransactTo::Create,
+            },
+            value: ctx.value,
+            data: ctx.data,
+            nonce: Some(ctx.nonce),
+            ..Default::default()
+        };
+
+        validation::validate_transaction(
+            &self.persistent_db,
+            block_env,
+            ctx.spec_id,
+            tx_env,
+            ctx.pending_nonce,
+        )
+        .map_err(|err| EVMError::Database(format!("validation failed: {}", err)))
+    }
+
+    pub fn code_at(
+        &mut self,
+        address: Address,
+        state: StateSelector,
+    ) -> std::result::Result<Bytes, EVMError<String>> {
+        let state = self.state_ref(state);
+
+        let account = state
+            .basic_ref(address)
             .map_err(|err| EVMError::Database(format!("account lookup failed: {}", err).into()))?;
 
         match account {
             Some(account) => {
-                let code = self
-                    .persistent_db
-                    .code_by_hash(account.code_hash)
-                    .map_err(|err| {
+                let code = match account.code {
+                    Some(code) => code,
+                    None => state.code_by_hash_ref(account.code_hash).map_err(|err| {
                         EVMError::Database(format!("code lookup failed: {}", err).into())
-                    })?;
+                    })?,
+                };
 
                 Ok(match code {
                     Bytecode::LegacyRaw(code) => code,
@@ -116,8 +172,9 @@ impl EvmInner {
         &mut self,
         address: Address,
         slot: U256,
+        state: StateSelector,
     ) -> std::result::Result<U256, EVMError<String>> {
-        match self.persistent_db.storage(address, slot) {
+        match self.state_ref(state).storage_ref(address, slot) {
             Ok(slot) => Ok(slot),
             Err(err) => Err(EVMError::Database(
                 format!("storage lookup failed: {}", err).into(),
@@ -125,182 +182,274 @@ impl EvmInner {
         }
     }
 
+    /// Prepares the genesis allocation as the start of the commit at height 0 and returns its
+    /// state hash, or the state hash of height 0 if it is already committed.
     pub fn initialize_genesis(
         &mut self,
         genesis_ctx: GenesisContext,
-    ) -> std::result::Result<(), EVMError<String>> {
+    ) -> std::result::Result<String, EVMError<String>> {
         self.persistent_db.set_genesis_info(GenesisInfo {
-            account: genesis_ctx.account,
             deployer_account: genesis_ctx.deployer_account,
             validator_contract: genesis_ctx.validator_contract,
-            initial_supply: genesis_ctx.initial_supply,
         });
 
-        Ok(())
+        let genesis_commit = if self.persistent_db.is_height_committed(0) {
+            None
+        } else {
+            Some(
+                state_commit::genesis_commit(&mut self.persistent_db, genesis_ctx.alloc)
+                    .map_err(|err| EVMError::Database(format!("genesis failed: {err}")))?,
+            )
+        };
+
+        let state_hash = state_hash::calculate(
+            &mut self.persistent_db,
+            genesis_commit
+                .clone()
+                .unwrap_or_else(|| PendingCommit::new(CommitKey(0, 0))),
+            B256::ZERO,
+        )
+        .map_err(|err| EVMError::Database(format!("genesis state_hash failed: {err}")))?;
+
+        self.genesis_commit = genesis_commit;
+
+        Ok(state_hash.encode_hex())
     }
 
+    /// Calculates the top validators of the consensus contract and returns them in order.
     pub fn calculate_top_validators(
         &mut self,
         ctx: CalculateTopValidatorsContext,
-    ) -> std::result::Result<(), EVMError<String>> {
-        assert!(
-            self.pending_commit
-                .as_ref()
-                .is_some_and(|c| c.key == ctx.commit_key),
-            "calculate_top_validators pending commit key mismatch {:?} - {:?}",
-            self.pending_commit.as_ref().map(|c| c.key),
-            ctx.commit_key
-        );
+    ) -> std::result::Result<Vec<Validator>, EVMError<String>> {
+        self.check_system_call_commit_key(ctx.commit_key)?;
 
-        let genesis_info = self
+        self.call_consensus(
+            SystemCallBlock {
+                timestamp: ctx.timestamp,
+                coinbase: ctx.validator_address,
+                spec_id: ctx.spec_id,
+            },
+            Consensus::calculateTopValidatorsCall {
+                n: ctx.active_validators,
+            },
+        )?;
+
+        let validators = self
+            .view_consensus(
+                StateSelector::Pending,
+                ctx.spec_id,
+                Consensus::getTopValidatorsCall {},
+            )?
+            ._0;
+
+        // keep a snapshot of the set with the commit
+        let pending_commit = self.pending_commit.as_mut().expect("ok");
+        pending_commit.validator_set = Some(ValidatorSet {
+            height: ctx.commit_key.0,
+            round: ctx.commit_key.1,
+            validators: validators.iter().cloned().map(Into::into).collect(),
+        });
+
+        Ok(validators)
+    }
+
+    pub fn update_rewards_and_votes(
+        &mut self,
+        ctx: UpdateRewardsAndVotesContext,
+    ) -> std::result::Result<Vec<SystemCallOutput<Consensus::updateVotersReturn>>, EVMError<String>>
+    {
+        self.check_system_call_commit_key(ctx.commit_key)?;
+
+        let pending_commit = self.pending_commit.as_mut().expect("ok");
+
+        let mut rewards = Vec::with_capacity(ctx.rewards.len() + 1);
+        rewards.push(Reward {
+            address: ctx.validator_address,
+            amount: ctx.block_reward,
+            kind: "block".into(),
+        });
+        rewards.extend(ctx.rewards);
+
+        state_commit::apply_reward_schedule(&mut self.persistent_db, pending_commit, rewards)
+            .and_then(|_| {
+                state_commit::distribute_fees(
+                    &mut self.persistent_db,
+                    pending_commit,
+                    ctx.validator_address,
+                )
+            })
+            .map_err(|err| EVMError::Database(format!("apply_rewards failed: {err}")))?;
+
+        // call into consensus contract to update the votes of voters whose balance changed
+        let validator_contract = self
             .persistent_db
             .genesis_info
             .as_ref()
             .expect("genesis info")
-            .clone();
-
-        let abi = ethers_contract::BaseContract::from(
-            ethers_core::abi::parse_abi(&["function calculateTopValidators(uint8 n) external"])
-                .expect("encode abi"),
-        );
+            .validator_contract;
+        let voters =
+            voters::changed_voters(&self.persistent_db, pending_commit, validator_contract)
+                .map_err(|err| EVMError::Database(format!("changed_voters failed: {err}")))?;
+
+        let block = SystemCallBlock {
+            timestamp: ctx.timestamp,
+            coinbase: ctx.validator_address,
+            spec_id: ctx.spec_id,
+        };
 
-        // encode abi into Bytes
-        let calldata = abi
-            .encode("calculateTopValidators", ctx.active_validators)
-            .expect("encode calculateTopValidators");
+        let mut outputs = Vec::new();
+        for chunk in voters.chunks(voters::UPDATE_VOTERS_CHUNK_SIZE) {
+            outputs.push(self.call_consensus(
+                block.clone(),
+                Consensus::updateVotersCall {
+                    voters: chunk.to_vec(),
+                },
+            )?);
+        }
 
-        let nonce = self
-            .get_account_nonce(genesis_info.deployer_account)
-            .map_err(|err| EVMError::Database(format!("get_account_nonce: {err}").into()))?;
+        Ok(outputs)
+    }
 
-        match self.transact_evm(ExecutionContext {
-            block_context: Some(BlockContext {
-                commit_key: ctx.commit_key,
-                gas_limit: U256::MAX,
-                timestamp: ctx.timestamp,
-                validator_address: ctx.validator_address,
-            }),
-            caller: genesis_info.deployer_account,
-            recipient: Some(genesis_info.validator_contract),
-            data: revm::primitives::Bytes::from(calldata.0),
-            value: U256::ZERO,
-            nonce: Some(nonce),
-            gas_limit: Some(u64::MAX),
-            gas_price: None,
-            spec_id: ctx.spec_id,
-            tx_hash: None,
-        }) {
-            Ok(receipt) => {
-                println!(
-                    "calculate_top_validators {:?} {:?}",
-                    ctx.commit_key, receipt
-                );
-                assert!(
-                    receipt.is_success(),
-                    "calculate_top_validators unsuccessful"
-                );
-                Ok(())
+    pub fn apply_penalties(
+        &mut self,
+        ctx: ApplyPenaltiesContext,
+    ) -> std::result::Result<
+        Vec<SystemCallOutput<Consensus::penalizeValidatorReturn>>,
+        EVMError<String>,
+    > {
+        self.check_system_call_commit_key(ctx.commit_key)?;
+
+        let pending_commit = self.pending_commit.as_mut().expect("ok");
+
+        let mut penalized = Vec::<Address>::new();
+        for penalty in &ctx.penalties {
+            if !penalized.contains(&penalty.address) {
+                penalized.push(penalty.address);
             }
-            Err(err) => Err(EVMError::Database(
-                format!("calculate_top_validators failed: {}", err).into(),
-            )),
         }
+
+        state_commit::apply_penalties(
+            &mut self.persistent_db,
+            pending_commit,
+            ctx.penalties,
+            ctx.mode,
+        )
+        .map_err(|err| EVMError::Database(format!("apply_penalties failed: {err}")))?;
+
+        // call into consensus contract to mark the validators as penalized
+        let block = SystemCallBlock {
+            timestamp: ctx.timestamp,
+            coinbase: ctx.validator_address,
+            spec_id: ctx.spec_id,
+        };
+
+        penalized
+            .into_iter()
+            .map(|addr| {
+                self.call_consensus(block.clone(), Consensus::penalizeValidatorCall { addr })
+            })
+            .collect()
     }
 
-    pub fn update_rewards_and_votes(
+    pub fn get_validator(
         &mut self,
-        ctx: UpdateRewardsAndVotesContext,
-    ) -> std::result::Result<(), EVMError<String>> {
-        assert!(
-            self.pending_commit
-                .as_ref()
-                .is_some_and(|c| c.key == ctx.commit_key),
-            "update_rewards_and_votes pending commit key mismatch {:?} - {:?}",
-            self.pending_commit.as_ref().map(|c| c.key),
-            ctx.commit_key
-        );
+        address: Address,
+        state: StateSelector,
+    ) -> std::result::Result<Option<Validator>, EVMError<String>> {
+        let registered = self
+            .view_consensus(
+                state,
+                ctx::LATEST_SPEC_ID,
+                Consensus::isValidatorRegisteredCall { addr: address },
+            )?
+            ._0;
+        if !registered {
+            return Ok(None);
+        }
 
-        let mut pending_commit = self.pending_commit.as_mut().expect("ok");
+        self.view_consensus(
+            state,
+            ctx::LATEST_SPEC_ID,
+            Consensus::getValidatorCall { _addr: address },
+        )
+        .map(|validator| Some(validator._0))
+    }
 
-        let genesis_info = self
+    pub fn get_all_validators(
+        &mut self,
+        state: StateSelector,
+    ) -> std::result::Result<Vec<Validator>, EVMError<String>> {
+        self.view_consensus(
+            state,
+            ctx::LATEST_SPEC_ID,
+            Consensus::getAllValidatorsCall {},
+        )
+        .map(|validators| validators._0)
+    }
+
+    pub fn get_voter_of(
+        &mut self,
+        address: Address,
+        state: StateSelector,
+    ) -> std::result::Result<Option<Vote>, EVMError<String>> {
+        let validator_contract = self
             .persistent_db
             .genesis_info
             .as_ref()
             .expect("genesis info")
-            .clone();
-
-        let mut rewards = HashMap::<Address, u128>::new();
-        rewards.insert(ctx.validator_address, ctx.block_reward);
-
-        match state_commit::apply_rewards(&mut self.persistent_db, &mut pending_commit, rewards) {
-            Ok(_) => {
-                // call into consensus contract to update votes
-                let voters = pending_commit
-                    .cache
-                    .accounts
-                    .keys()
-                    .map(|k| ethers_core::types::Address::from_slice(k.0.as_slice()))
-                    .collect::<Vec<ethers_core::types::Address>>();
-
-                let abi = ethers_contract::BaseContract::from(
-                    ethers_core::abi::parse_abi(&[
-                        "function updateVoters(address[] calldata voters) external",
-                    ])
-                    .expect("encode abi"),
-                );
+            .validator_contract;
+
+        SystemCaller::new(&self.persistent_db, &self.precompiles)
+            .vote_of(
+                self.state_ref(state),
+                self.view_height(state)?,
+                ctx::LATEST_SPEC_ID,
+                validator_contract,
+                address,
+            )
+            .map_err(|err| EVMError::Database(err.to_string()))
+    }
 
-                // encode abi into Bytes
-                let calldata = abi
-                    .encode("updateVoters", voters.clone())
-                    .expect("encode updateVoters");
+    pub fn get_registered_validators_count(
+        &mut self,
+        state: StateSelector,
+    ) -> std::result::Result<U256, EVMError<String>> {
+        self.view_consensus(
+            state,
+            ctx::LATEST_SPEC_ID,
+            Consensus::registeredValidatorsCountCall {},
+        )
+        .map(|count| count._0)
+    }
 
-                let nonce = self
-                    .get_account_nonce(genesis_info.deployer_account)
-                    .map_err(|err| {
-                        EVMError::Database(format!("get_account_nonce: {err}").into())
-                    })?;
+    pub fn get_validator_set_at(
+        &mut self,
+        height: u64,
+    ) -> std::result::Result<Option<ValidatorSet>, EVMError<String>> {
+        self.persistent_db
+            .get_validator_set_at(height)
+            .map_err(|err| EVMError::Database(format!("validator set lookup failed: {err}")))
+    }
 
-                match self.transact_evm(ExecutionContext {
-                    block_context: Some(BlockContext {
-                        commit_key: ctx.commit_key,
-                        gas_limit: U256::MAX,
-                        timestamp: ctx.timestamp,
-                        validator_address: ctx.validator_address,
-                    }),
-                    caller: genesis_info.deployer_account,
-                    recipient: Some(genesis_info.validator_contract),
-                    data: revm::primitives::Bytes::from(calldata.0),
-                    value: U256::ZERO,
-                    nonce: Some(nonce),
-                    gas_limit: Some(u64::MAX),
-                    gas_price: None,
-                    spec_id: ctx.spec_id,
-                    tx_hash: None,
-                }) {
-                    Ok(receipt) => {
-                        println!(
-                            "vote_update {:?} {:?} {:?}",
-                            ctx.commit_key, receipt, voters
-                        );
-                        assert!(receipt.is_success(), "vote_update unsuccessful");
-                        Ok(())
-                    }
-                    Err(err) => Err(EVMError::Database(
-                        format!("vote_update failed: {err}").into(),
-                    )),
-                }
-            }
-            Err(err) => Err(EVMError::Database(
-                format!("apply_rewards failed: {err}").into(),
-            )),
+    /// Receipt of a committed transaction or system call, `None` if the commit at `height` does
+    /// not contain `tx_hash`.
+    pub fn get_receipt(
+        &mut self,
+        height: u64,
+        tx_hash: B256,
+    ) -> std::result::Result<Option<TxReceipt>, EVMError<String>> {
+        match self.persistent_db.get_committed_receipt(height, tx_hash) {
+            Ok((_, receipt)) => Ok(receipt),
+            Err(err) => Err(EVMError::Database(format!("receipt lookup failed: {err}"))),
         }
     }
 
     pub fn get_account_info(
         &mut self,
         address: Address,
+        state: StateSelector,
     ) -> std::result::Result<AccountInfo, EVMError<String>> {
-        match self.persistent_db.basic(address) {
+        match self.state_ref(state).basic_ref(address) {
             Ok(account) => Ok(account.unwrap_or_default()),
             Err(err) => Err(EVMError::Database(
                 format!("account lookup failed: {}", err).into(),
@@ -315,94 +464,152 @@ impl EvmInner {
         let commit_key = tx_ctx.block_context.commit_key;
 
         // Check if already committed and return existing receipt
-        let (committed, receipt) = self
-            .persistent_db
-            .get_committed_receipt(commit_key.0, tx_ctx.tx_hash)
-            .map_err(|err| EVMError::Database(format!("commit receipt lookup: {}", err).into()))?;
-
-        if committed {
-            match receipt {
-                Some(receipt) => return Ok(receipt),
-                None => {
-                    return Err(EVMError::Database(
-                        "found commit, but tx hash is missing".into(),
-                    ))
-                }
-            }
+        if let Some(receipt) = self.committed_receipt(commit_key.0, tx_ctx.tx_hash)? {
+            return Ok(receipt);
         }
 
-        if let Some(pending) = self.pending_commit.as_ref() {
-            assert!(pending.key == commit_key, "pending commit key mismatch");
+        self.check_pending_commit_key(commit_key)?;
+
+        let outcome = self
+            .execute(
+                &tx_ctx.block_context,
+                tx_ctx.spec_id,
+                vec![tx_ctx.transaction()],
+                false,
+            )
+            .outcomes
+            .pop()
+            .expect("outcome");
+
+        self.map_outcome(tx_ctx.tx_hash, tx_ctx.gas_limit, outcome)
+    }
+
+    /// Processes all transactions of the batch or none: if any of them fails, the pending
+    /// commit is restored to its state before the batch.
+    pub fn process_batch(
+        &mut self,
+        ctx: ProcessBatchContext,
+    ) -> std::result::Result<ProcessBatchResult, EVMError<String>> {
+        let checkpoint = self.pending_commit.clone();
+
+        let result = self.try_process_batch(ctx);
+        if result.is_err() {
+            self.pending_commit = checkpoint;
         }
 
-        let gas_limit = tx_ctx.gas_limit;
-        let result = self.transact_evm(tx_ctx.into());
+        result
+    }
 
-        match result {
-            Ok(result) => {
-                let receipt = map_execution_result(result);
-                Ok(receipt)
-            }
-            Err(err) => {
-                match err {
-                    EVMError::Transaction(err) => {
-                        match err {
-                            revm::primitives::InvalidTransaction::CallGasCostMoreThanGasLimit => {
-                                return Ok(TxReceipt {
-                                    gas_used: gas_limit,
-                                    ..Default::default()
-                                });
-                            }
-                            revm::primitives::InvalidTransaction::NonceTooHigh { .. }
-                            | revm::primitives::InvalidTransaction::NonceTooLow { .. } => {
-                                return Err(EVMError::Transaction(err));
-                            }
-                            revm::primitives::InvalidTransaction::LackOfFundForMaxFee {
-                                fee,
-                                balance,
-                            } => {
-                                todo!("lack of funds (fee={} balance={})", fee, balance);
-                            }
-                            // revm::primitives::InvalidTransaction::PriorityFeeGreaterThanMaxFee => todo!(),
-                            // revm::primitives::InvalidTransaction::GasPriceLessThanBasefee => todo!(),
-                            // revm::primitives::InvalidTransaction::CallerGasLimitMoreThanBlock => todo!(),
-                            // revm::primitives::InvalidTransaction::RejectCallerWithCode => todo!(),
-                            // revm::primitives::InvalidTransaction::OverflowPaymentInTransaction => todo!(),
-                            // revm::primitives::InvalidTransaction::NonceOverflowInTransaction => todo!(),
-                            // revm::primitives::InvalidTransaction::CreateInitCodeSizeLimit => todo!(),
-                            // revm::primitives::InvalidTransaction::InvalidChainId => todo!(),
-                            // revm::primitives::InvalidTransaction::AccessListNotSupported => todo!(),
-                            // revm::primitives::InvalidTransaction::MaxFeePerBlobGasNotSupported => todo!(),
-                            // revm::primitives::InvalidTransaction::BlobVersionedHashesNotSupported => todo!(),
-                            // revm::primitives::InvalidTransaction::BlobGasPriceGreaterThanMax => todo!(),
-                            // revm::primitives::InvalidTransaction::EmptyBlobs => todo!(),
-                            // revm::primitives::InvalidTransaction::BlobCreateTransaction => todo!(),
-                            // revm::primitives::InvalidTransaction::TooManyBlobs { max, have } => todo!(),
-                            // revm::primitives::InvalidTransaction::BlobVersionNotSupported => todo!(),
-                            // revm::primitives::InvalidTransaction::EofInitcodesNotSupported => todo!(),
-                            // revm::primitives::InvalidTransaction::EofInitcodesNumberLimit => todo!(),
-                            // revm::primitives::InvalidTransaction::EofInitcodesSizeLimit => todo!(),
-                            // revm::primitives::InvalidTransaction::EofCrateShouldHaveToAddress => todo!(),
-                            _ => {
-                                todo!("unhandled tx err {:?}", err);
-                            }
-                        }
-                    }
-                    // EVMError::Header(_) => todo!(),
-                    // EVMError::Database(_) => todo!(),
-                    // EVMError::Custom(_) => todo!(),
-                    _ => {
-                        todo!("unhandled evm err {:?}", err);
-                    }
-                }
+    fn try_process_batch(
+        &mut self,
+        ctx: ProcessBatchContext,
+    ) -> std::result::Result<ProcessBatchResult, EVMError<String>> {
+        let transactions = ctx
+            .decode_transactions()
+            .map_err(|err| EVMError::Database(format!("invalid batch: {}", err)))?;
+
+        let commit_key = ctx.block_context.commit_key;
+
+        // a replayed block returns what was committed, its rewards and fees are applied already
+        if self.persistent_db.is_height_committed(commit_key.0) {
+            let mut receipts = Vec::with_capacity(transactions.len());
+            for tx_ctx in &transactions {
+                let receipt = self
+                    .committed_receipt(commit_key.0, tx_ctx.tx_hash)?
+                    .ok_or_else(|| {
+                        EVMError::Database(format!(
+                            "missing receipt of {} at committed height {}",
+                            tx_ctx.tx_hash, commit_key.0
+                        ))
+                    })?;
+                receipts.push(receipt);
             }
+
+            let state_hash = match ctx.current_hash {
+                Some(current_hash) => Some(
+                    state_hash::calculate(
+                        &mut self.persistent_db,
+                        PendingCommit::new(commit_key),
+                        current_hash,
+                    )
+                    .map_err(|err| EVMError::Database(format!("state_hash failed: {err}")))?
+                    .encode_hex(),
+                ),
+                None => None,
+            };
+
+            return Ok(ProcessBatchResult {
+                receipts,
+                state_hash,
+            });
+        }
+
+        self.check_pending_commit_key(commit_key)?;
+
+        let output = self.execute(
+            &ctx.block_context,
+            ctx.spec_id,
+            transactions.iter().map(TxContext::transaction).collect(),
+            self.parallel_execution,
+        );
+
+        let mut receipts = Vec::with_capacity(transactions.len());
+        for (index, (tx_ctx, outcome)) in transactions.iter().zip(output.outcomes).enumerate() {
+            let receipt = self
+                .map_outcome(tx_ctx.tx_hash, tx_ctx.gas_limit, outcome)
+                .map_err(|err| {
+                    EVMError::Database(format!(
+                        "batch tx {} ({}) failed: {}",
+                        index, tx_ctx.tx_hash, err
+                    ))
+                })?;
+
+            receipts.push(receipt);
+        }
+
+        let BlockContext {
+            timestamp,
+            validator_address,
+            ..
+        } = ctx.block_context;
+
+        if let Some(block_reward) = ctx.block_reward {
+            self.update_rewards_and_votes(UpdateRewardsAndVotesContext {
+                commit_key,
+                timestamp,
+                block_reward,
+                validator_address,
+                spec_id: ctx.spec_id,
+                rewards: ctx.rewards,
+            })?;
+        } else if let Some(pending_commit) = self.pending_commit.as_mut() {
+            state_commit::distribute_fees(
+                &mut self.persistent_db,
+                pending_commit,
+                validator_address,
+            )
+            .map_err(|err| EVMError::Database(format!("distribute_fees failed: {err}")))?;
         }
+
+        let state_hash = match ctx.current_hash {
+            Some(current_hash) => Some(self.state_hash(commit_key, current_hash)?),
+            None => None,
+        };
+
+        Ok(ProcessBatchResult {
+            receipts,
+            state_hash,
+        })
     }
 
     pub fn commit(
         &mut self,
         commit_key: CommitKey,
-    ) -> std::result::Result<Vec<AccountUpdate>, EVMError<String>> {
+    ) -> std::result::Result<CommitResult, EVMError<String>> {
+        if commit_key.0 == 0 && self.persistent_db.is_height_committed(0) {
+            self.genesis_commit = None;
+        }
+
         if self.persistent_db.is_height_committed(commit_key.0) {
             self.drop_pending_commit();
             return Ok(Default::default());
@@ -423,20 +630,47 @@ impl EvmInner {
             ));
         }
 
-        let outcome = match self.take_pending_commit() {
+        // the genesis is committed at height 0 even without any transactions
+        let pending_commit = self.take_pending_commit().or_else(|| {
+            (commit_key.0 == 0 && self.genesis_commit.is_some())
+                .then(|| self.new_pending_commit(commit_key))
+        });
+
+        let outcome = match pending_commit {
             Some(pending_commit) => {
                 // println!(
                 //     "committing {:?} with {} transactions",
                 //     commit_key,
                 //     pending_commit.diff.len(),
                 // );
-                state_commit::commit_to_db(&mut self.persistent_db, pending_commit)
+                let fees = pending_commit.distributed_fees;
+                let rewards = pending_commit.rewards.clone();
+                let penalties = pending_commit.penalties.clone();
+                let system_receipts = map_system_results(
+                    &pending_commit.system_results,
+                    pending_commit.positions.len() as u64,
+                    pending_commit.gas_used,
+                );
+                state_commit::commit_to_db(&mut self.persistent_db, pending_commit).map(
+                    |dirty_accounts| CommitResult {
+                        dirty_accounts,
+                        fees,
+                        rewards,
+                        penalties,
+                        system_receipts,
+                    },
+                )
             }
             None => Ok(Default::default()),
         };
 
         match outcome {
-            Ok(result) => Ok(result),
+            Ok(result) => {
+                if commit_key.0 == 0 {
+                    self.genesis_commit = None;
+                }
+                Ok(result)
+            }
             Err(err) => Err(EVMError::Database(format!("commit failed: {}", err).into())),
         }
     }
@@ -454,13 +688,10 @@ impl EvmInner {
             self.drop_pending_commit();
         }
 
-        let pending_commit = self
-            .pending_commit
-            .get_or_insert_with(|| PendingCommit {
-                key: commit_key,
-                ..Default::default()
-            })
-            .clone();
+        if self.pending_commit.is_none() {
+            self.pending_commit = Some(self.new_pending_commit(commit_key));
+        }
+        let pending_commit = self.pending_commit.clone().expect("pending commit");
 
         let result = state_hash::calculate(&mut self.persistent_db, pending_commit, current_hash);
 
@@ -472,112 +703,246 @@ impl EvmInner {
         }
     }
 
-    fn transact_evm(
-        &mut self,
-        ctx: ExecutionContext,
-    ) -> std::result::Result<ExecutionResult, EVMError<mainsail_evm_core::db::Error>> {
-        let mut state_builder = State::builder().with_bundle_update();
+    pub fn stats(&mut self) -> std::result::Result<StatsResult, EVMError<String>> {
+        let db = self
+            .persistent_db
+            .stats()
+            .map_err(|err| EVMError::Database(format!("stats failed: {}", err)))?;
 
-        if let Some(commit_key) = ctx.block_context.as_ref().map(|b| b.commit_key) {
-            let pending_commit = self
-                .pending_commit
-                .get_or_insert_with(|| PendingCommit::new(commit_key));
+        Ok(StatsResult {
+            db,
+            pending: self.pending_commit.as_ref().map(|pending| pending.stats()),
+        })
+    }
 
-            state_builder =
-                state_builder.with_cached_prestate(std::mem::take(&mut pending_commit.cache));
+    // Executes transactions of the block on top of the pending commit.
+    fn execute(
+        &mut self,
+        block_ctx: &BlockContext,
+        spec_id: SpecId,
+        transactions: Vec<execution::Transaction>,
+        parallel: bool,
+    ) -> execution::ExecutionOutput {
+        if self.pending_commit.is_none() {
+            self.pending_commit = Some(self.new_pending_commit(block_ctx.commit_key));
         }
+        let pending_commit = self.pending_commit.as_mut().expect("pending commit");
 
-        let state_db = state_builder
-            .with_database(WrapDatabaseRef(&self.persistent_db))
-            .build();
-
-        let mut evm = Evm::builder()
-            .with_db(state_db)
-            .with_spec_id(ctx.spec_id)
-            .modify_block_env(|block_env| {
-                let Some(block_ctx) = ctx.block_context.as_ref() else {
-                    return;
-                };
+        if let Some(randomness) = block_ctx.randomness {
+            pending_commit.randomness = randomness;
+        }
 
-                block_env.number = U256::from(block_ctx.commit_key.0);
-                block_env.coinbase = block_ctx.validator_address;
-                block_env.timestamp = block_ctx.timestamp;
-                block_env.gas_limit = block_ctx.gas_limit;
-                block_env.difficulty = U256::ZERO;
-            })
-            .modify_tx_env(|tx_env| {
-                tx_env.gas_limit = ctx.gas_limit.unwrap_or_else(|| 15_000_000);
-                tx_env.gas_price = ctx.gas_price.unwrap_or_else(|| U256::ZERO);
-                tx_env.caller = ctx.caller;
-                tx_env.value = ctx.value;
-                tx_env.nonce = ctx.nonce;
-                tx_env.transact_to = match ctx.recipient {
-                    Some(recipient) => revm::primitives::TransactTo::Call(recipient),
-                    None => revm::primitives::TransactTo::Create,
-                };
+        // fees are withheld from the coinbase and distributed at the end of the commit
+        if block_ctx.fee_distribution.is_some() {
+            pending_commit.fee_distribution = block_ctx.fee_distribution;
+        }
 
-                tx_env.data = ctx.data;
-            })
-            .build();
+        let execute = if parallel {
+            execution::execute_parallel
+        } else {
+            execution::execute_sequential
+        };
 
-        let result = evm.transact();
+        execute(
+            &self.persistent_db,
+            pending_commit,
+            &block_ctx.block_env(pending_commit.randomness),
+            spec_id,
+            &self.precompiles,
+            Some(&block_ctx.consensus_data()),
+            transactions,
+        )
+    }
 
-        match result {
+    fn map_outcome(
+        &self,
+        tx_hash: B256,
+        gas_limit: u64,
+        outcome: execution::TxOutcome,
+    ) -> std::result::Result<TxReceipt, EVMError<String>> {
+        match outcome {
             Ok(result) => {
-                let ResultAndState { state, result } = result;
+                let position = self
+                    .pending_commit
+                    .as_ref()
+                    .and_then(|pending| pending.positions.get(&tx_hash).copied())
+                    .unwrap_or_default();
 
-                // Update state if transaction is part of a commit
-                if let Some(commit_key) = ctx.block_context.as_ref().map(|b| b.commit_key) {
-                    if let Some(pending_commit) = &mut self.pending_commit {
-                        assert_eq!(commit_key, pending_commit.key);
-
-                        let state_db = evm.db_mut();
+                Ok(map_execution_result(result).with_position(position))
+            }
+            // the transaction is included but consumes its whole gas limit
+            Err(EVMError::Transaction(
+                revm::primitives::InvalidTransaction::CallGasCostMoreThanGasLimit,
+            )) => Ok(TxReceipt {
+                gas_used: gas_limit,
+                ..Default::default()
+            }),
+            Err(err) => Err(err.map_db_err(|err| err.to_string())),
+        }
+    }
 
-                        state_db.commit(state);
+    // Returns the receipt of the transaction if its height is already committed.
+    fn committed_receipt(
+        &self,
+        height: u64,
+        tx_hash: B256,
+    ) -> std::result::Result<Option<TxReceipt>, EVMError<String>> {
+        let (committed, receipt) = self
+            .persistent_db
+            .get_committed_receipt(height, tx_hash)
+            .map_err(|err| EVMError::Database(format!("commit receipt lookup: {}", err)))?;
+
+        match (committed, receipt) {
+            (false, _) => Ok(None),
+            (true, Some(receipt)) => Ok(Some(receipt)),
+            (true, None) => Err(EVMError::Database(
+                "found commit, but tx hash is missing".into(),
+            )),
+        }
+    }
 
-                        pending_commit.cache = std::mem::take(&mut state_db.cache);
+    fn check_pending_commit_key(
+        &self,
+        commit_key: CommitKey,
+    ) -> std::result::Result<(), EVMError<String>> {
+        match self.pending_commit.as_ref() {
+            Some(pending) if pending.key != commit_key => Err(EVMError::Database(format!(
+                "pending commit key mismatch: {:?} - {:?}",
+                pending.key, commit_key
+            ))),
+            _ => Ok(()),
+        }
+    }
 
-                        if let Some(tx_hash) = ctx.tx_hash {
-                            pending_commit.results.insert(tx_hash, result.clone());
-                        }
+    // System calls always run against a pending commit prepared for the same key.
+    fn check_system_call_commit_key(
+        &self,
+        commit_key: CommitKey,
+    ) -> std::result::Result<(), EVMError<String>> {
+        match self.pending_commit.as_ref() {
+            Some(pending) if pending.key == commit_key => Ok(()),
+            pending => Err(EVMError::Database(format!(
+                "pending commit key mismatch: {:?} - {:?}",
+                pending.map(|c| c.key),
+                commit_key
+            ))),
+        }
+    }
 
-                        pending_commit.transitions.add_transitions(
-                            state_db
-                                .transition_state
-                                .take()
-                                .unwrap_or_default()
-                                .transitions
-                                .into_iter()
-                                .collect::<Vec<(Address, TransitionAccount)>>(),
-                        );
-                    }
-                }
+    // Calls outside of a commit see the precompiles of the next height and the randomness of
+    // the last committed one.
+    fn execute_view(
+        &self,
+        tx_ctx: &TxViewContext,
+    ) -> std::result::Result<ExecutionResult, EVMError<mainsail_evm_core::db::Error>> {
+        let (height, randomness) = match self
+            .persistent_db
+            .last_committed_height()
+            .map_err(EVMError::Database)?
+        {
+            Some(height) => (
+                height + 1,
+                self.persistent_db
+                    .get_committed_randomness(height)
+                    .map_err(EVMError::Database)?
+                    .unwrap_or_default(),
+            ),
+            None => (0, B256::ZERO),
+        };
 
-                Ok(result)
-            }
-            Err(err) => Err(err),
-        }
+        execution::execute_view(
+            &self.persistent_db,
+            &BlockEnv {
+                number: U256::from(height),
+                prevrandao: Some(randomness),
+                ..Default::default()
+            },
+            tx_ctx.spec_id,
+            &self.precompiles,
+            tx_ctx.tx_env(),
+        )
     }
 
-    fn get_account_nonce(
+    // Calls into the consensus contract as part of the pending commit.
+    fn call_consensus<C: SolCall>(
         &mut self,
-        account: Address,
-    ) -> std::result::Result<u64, mainsail_evm_core::db::Error> {
-        if let Some(pending) = &self.pending_commit {
-            if pending.cache.accounts.contains_key(&account) {
-                if let Some(cache) = pending.cache.accounts.get(&account) {
-                    if let Some(account) = &cache.account {
-                        return Ok(account.info.nonce);
-                    }
-                }
-            }
+        block: SystemCallBlock,
+        call: C,
+    ) -> std::result::Result<SystemCallOutput<C::Return>, EVMError<String>> {
+        let genesis_info = self
+            .persistent_db
+            .genesis_info
+            .as_ref()
+            .expect("genesis info");
+        let pending_commit = self.pending_commit.as_mut().expect("pending commit");
+
+        SystemCaller::new(&self.persistent_db, &self.precompiles)
+            .call(
+                pending_commit,
+                &block,
+                genesis_info.validator_contract,
+                call,
+            )
+            .map_err(|err| EVMError::Database(err.to_string()))
+    }
+
+    // Reads from the consensus contract without changing any state.
+    fn view_consensus<C: SolCall>(
+        &self,
+        state: StateSelector,
+        spec_id: SpecId,
+        call: C,
+    ) -> std::result::Result<C::Return, EVMError<String>> {
+        let genesis_info = self
+            .persistent_db
+            .genesis_info
+            .as_ref()
+            .expect("genesis info");
+
+        SystemCaller::new(&self.persistent_db, &self.precompiles)
+            .view(
+                self.state_ref(state),
+                self.view_height(state)?,
+                spec_id,
+                genesis_info.validator_contract,
+                call,
+            )
+            .map_err(|err| EVMError::Database(err.to_string()))
+    }
+
+    // Height views of the selected state are executed at.
+    fn view_height(&self, state: StateSelector) -> std::result::Result<u64, EVMError<String>> {
+        match (state, self.pending_commit.as_ref()) {
+            (StateSelector::Pending, Some(pending)) => Ok(pending.key.0),
+            _ => Ok(self
+                .persistent_db
+                .last_committed_height()
+                .map_err(|err| EVMError::Database(err.to_string()))?
+                .map_or(0, |height| height + 1)),
         }
+    }
 
-        if let Some(account_info) = self.persistent_db.basic(account)? {
-            return Ok(account_info.nonce);
+    fn state_ref(&self, state: StateSelector) -> PendingStateRef<'_, &PersistentDB> {
+        let cache = match state {
+            StateSelector::Committed => None,
+            StateSelector::Pending => self.pending_commit.as_ref().map(|pending| &pending.cache),
+        };
+
+        PendingStateRef {
+            db: &self.persistent_db,
+            cache,
         }
+    }
 
-        return Ok(Default::default());
+    // Height 0 starts from the genesis allocation, any other height from the committed state.
+    fn new_pending_commit(&self, commit_key: CommitKey) -> PendingCommit {
+        match &self.genesis_commit {
+            Some(genesis_commit) if commit_key.0 == 0 => PendingCommit {
+                key: commit_key,
+                ..genesis_commit.clone()
+            },
+            _ => PendingCommit::new(commit_key),
+        }
     }
 
     fn take_pending_commit(&mut self) -> Option<PendingCommit> {
@@ -599,10 +964,15 @@ pub struct JsEvmWrapper {
 #[napi]
 impl JsEvmWrapper {
     #[napi(constructor)]
-    pub fn new(path: JsString) -> Result<Self> {
+    pub fn new(path: JsString, options: Option<JsEvmOptions>) -> Result<Self> {
         let path = path.into_utf8()?.into_owned()?;
+        let options = match options {
+            Some(options) => EvmOptions::try_from(options)?,
+            None => Default::default(),
+        };
+
         Ok(JsEvmWrapper {
-            evm: Arc::new(tokio::sync::Mutex::new(EvmInner::new(path.into()))),
+            evm: Arc::new(tokio::sync::Mutex::new(EvmInner::new(path.into(), options))),
         })
     }
 
@@ -615,6 +985,19 @@ impl JsEvmWrapper {
         )
     }
 
+    #[napi(ts_return_type = "Promise<JsValidationResult>")]
+    pub fn validate_transaction(
+        &mut self,
+        node_env: Env,
+        ctx: JsValidateTransactionContext,
+    ) -> Result<JsObject> {
+        let ctx = ValidateTransactionContext::try_from(ctx)?;
+        node_env.execute_tokio_future(
+            Self::validate_transaction_async(self.evm.clone(), ctx),
+            |&mut node_env, result| Ok(result::JsValidationResult::new(&node_env, result)?),
+        )
+    }
+
     #[napi(ts_return_type = "Promise<JsProcessResult>")]
     pub fn process(&mut self, node_env: Env, tx_ctx: JsTransactionContext) -> Result<JsObject> {
         let tx_ctx = TxContext::try_from(tx_ctx)?;
@@ -624,7 +1007,16 @@ impl JsEvmWrapper {
         )
     }
 
-    #[napi(ts_return_type = "Promise<void>")]
+    #[napi(ts_return_type = "Promise<JsProcessBatchResult>")]
+    pub fn process_batch(&mut self, node_env: Env, ctx: JsProcessBatchContext) -> Result<JsObject> {
+        let ctx = ProcessBatchContext::try_from(ctx)?;
+        node_env.execute_tokio_future(
+            Self::process_batch_async(self.evm.clone(), ctx),
+            |&mut node_env, result| Ok(result::JsProcessBatchResult::new(&node_env, result)?),
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<string>")]
     pub fn initialize_genesis(
         &mut self,
         node_env: Env,
@@ -633,7 +1025,7 @@ impl JsEvmWrapper {
         let genesis_ctx = GenesisContext::try_from(genesis_ctx)?;
         node_env.execute_tokio_future(
             Self::initialize_genesis_async(self.evm.clone(), genesis_ctx),
-            |_, _| Ok(()),
+            |&mut node_env, result| node_env.create_string_from_std(result),
         )
     }
 
@@ -650,7 +1042,7 @@ impl JsEvmWrapper {
         )
     }
 
-    #[napi(ts_return_type = "Promise<void>")]
+    #[napi(ts_return_type = "Promise<Array<JsValidator>>")]
     pub fn calculate_top_validators(
         &mut self,
         node_env: Env,
@@ -659,7 +1051,12 @@ impl JsEvmWrapper {
         let ctx = CalculateTopValidatorsContext::try_from(ctx)?;
         node_env.execute_tokio_future(
             Self::calculate_top_validators_async(self.evm.clone(), ctx),
-            |_, _| Ok(()),
+            |&mut node_env, validators| {
+                validators
+                    .into_iter()
+                    .map(|validator| Ok(result::JsValidator::new(&node_env, validator)?))
+                    .collect::<Result<Vec<_>>>()
+            },
         )
     }
 
@@ -676,20 +1073,144 @@ impl JsEvmWrapper {
         )
     }
 
+    #[napi(ts_return_type = "Promise<void>")]
+    pub fn apply_penalties(
+        &mut self,
+        node_env: Env,
+        ctx: JsApplyPenaltiesContext,
+    ) -> Result<JsObject> {
+        let ctx = ApplyPenaltiesContext::try_from(ctx)?;
+        node_env.execute_tokio_future(
+            Self::apply_penalties_async(self.evm.clone(), ctx),
+            |_, _| Ok(()),
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<JsValidator | null>")]
+    pub fn get_validator(
+        &mut self,
+        node_env: Env,
+        address: JsString,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
+        let address = utils::create_address_from_js_string(address)?;
+        let state = ctx::parse_state_selector(state)?;
+        node_env.execute_tokio_future(
+            Self::get_validator_async(self.evm.clone(), address, state),
+            |&mut node_env, result| match result {
+                Some(validator) => Ok(Some(result::JsValidator::new(&node_env, validator)?)),
+                None => Ok(None),
+            },
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<Array<JsValidator>>")]
+    pub fn get_all_validators(
+        &mut self,
+        node_env: Env,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
+        let state = ctx::parse_state_selector(state)?;
+        node_env.execute_tokio_future(
+            Self::get_all_validators_async(self.evm.clone(), state),
+            |&mut node_env, validators| {
+                validators
+                    .into_iter()
+                    .map(|validator| Ok(result::JsValidator::new(&node_env, validator)?))
+                    .collect::<Result<Vec<_>>>()
+            },
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<JsVote | null>")]
+    pub fn get_voter_of(
+        &mut self,
+        node_env: Env,
+        address: JsString,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
+        let address = utils::create_address_from_js_string(address)?;
+        let state = ctx::parse_state_selector(state)?;
+        node_env.execute_tokio_future(
+            Self::get_voter_of_async(self.evm.clone(), address, state),
+            |&mut node_env, result| match result {
+                Some(vote) => Ok(Some(result::JsVote::new(&node_env, vote)?)),
+                None => Ok(None),
+            },
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<bigint>")]
+    pub fn get_registered_validators_count(
+        &mut self,
+        node_env: Env,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
+        let state = ctx::parse_state_selector(state)?;
+        node_env.execute_tokio_future(
+            Self::get_registered_validators_count_async(self.evm.clone(), state),
+            |&mut node_env, count| Ok(utils::convert_u256_to_bigint(&node_env, count)?),
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<JsValidatorSet | null>")]
+    pub fn get_validator_set_at(&mut self, node_env: Env, height: JsBigInt) -> Result<JsObject> {
+        let height = height.get_u64()?.0;
+        node_env.execute_tokio_future(
+            Self::get_validator_set_at_async(self.evm.clone(), height),
+            |&mut node_env, result| match result {
+                Some(validator_set) => {
+                    Ok(Some(result::JsValidatorSet::new(&node_env, validator_set)?))
+                }
+                None => Ok(None),
+            },
+        )
+    }
+
+    #[napi(ts_return_type = "Promise<JsTransactionReceipt | null>")]
+    pub fn get_receipt(
+        &mut self,
+        node_env: Env,
+        height: JsBigInt,
+        tx_hash: JsString,
+    ) -> Result<JsObject> {
+        let height = height.get_u64()?.0;
+        let tx_hash = utils::convert_string_to_b256(tx_hash)?;
+        node_env.execute_tokio_future(
+            Self::get_receipt_async(self.evm.clone(), height, tx_hash),
+            |&mut node_env, result| match result {
+                Some(receipt) => Ok(Some(result::JsTransactionReceipt::new(&node_env, receipt)?)),
+                None => Ok(None),
+            },
+        )
+    }
+
     #[napi(ts_return_type = "Promise<JsAccountInfo>")]
-    pub fn get_account_info(&mut self, node_env: Env, address: JsString) -> Result<JsObject> {
+    pub fn get_account_info(
+        &mut self,
+        node_env: Env,
+        address: JsString,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
         let address = utils::create_address_from_js_string(address)?;
+        let state = ctx::parse_state_selector(state)?;
         node_env.execute_tokio_future(
-            Self::get_account_info_async(self.evm.clone(), address),
+            Self::get_account_info_async(self.evm.clone(), address, state),
             |&mut node_env, result| Ok(result::JsAccountInfo::new(&node_env, result)?),
         )
     }
 
     #[napi(ts_return_type = "Promise<string>")]
-    pub fn code_at(&mut self, node_env: Env, address: JsString) -> Result<JsObject> {
+    pub fn code_at(
+        &mut self,
+        node_env: Env,
+        address: JsString,
+        state: Option<JsString>,
+    ) -> Result<JsObject> {
         let address = utils::create_address_from_js_string(address)?;
+        let state = ctx::parse_state_selector(state)?;
         node_env.execute_tokio_future(
-            Self::code_at_async(self.evm.clone(), address),
+            Self::code_at_async(self.evm.clone(), address, state),
             |&mut node_env, result| Ok(node_env.create_string_from_std(result)?),
         )
     }
@@ -700,11 +1221,13 @@ impl JsEvmWrapper {
         node_env: Env,
         address: JsString,
         slot: JsBigInt,
+        state: Option<JsString>,
     ) -> Result<JsObject> {
         let address = utils::create_address_from_js_string(address)?;
         let slot = utils::convert_bigint_to_u256(slot)?;
+        let state = ctx::parse_state_selector(state)?;
         node_env.execute_tokio_future(
-            Self::storage_at_async(self.evm.clone(), address, slot),
+            Self::storage_at_async(self.evm.clone(), address, slot, state),
             |&mut node_env, result| Ok(node_env.create_string_from_std(result)?),
         )
     }
@@ -733,6 +1256,14 @@ impl JsEvmWrapper {
         )
     }
 
+    #[napi(ts_return_type = "Promise<JsStats>")]
+    pub fn stats(&mut self, node_env: Env) -> Result<JsObject> {
+        node_env.execute_tokio_future(
+            Self::stats_async(self.evm.clone()),
+            |&mut node_env, result| Ok(result::JsStats::new(&node_env, result)?),
+        )
+    }
+
     async fn view_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         view_ctx: TxViewContext,
@@ -741,6 +1272,19 @@ impl JsEvmWrapper {
         lock.view(view_ctx)
     }
 
+    async fn validate_transaction_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        ctx: ValidateTransactionContext,
+    ) -> Result<TxValidation> {
+        let mut lock = evm.lock().await;
+        let result = lock.validate_transaction(ctx);
+
+        match result {
+            Ok(result) => Result::Ok(result),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
     async fn process_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         tx_ctx: TxContext,
@@ -754,12 +1298,107 @@ impl JsEvmWrapper {
         }
     }
 
+    async fn process_batch_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        ctx: ProcessBatchContext,
+    ) -> Result<ProcessBatchResult> {
+        let mut lock = evm.lock().await;
+        let result = lock.process_batch(ctx);
+
+        match result {
+            Ok(result) => Result::Ok(result),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_validator_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        address: Address,
+        state: StateSelector,
+    ) -> Result<Option<Validator>> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_validator(address, state);
+
+        match result {
+            Ok(validator) => Result::Ok(validator),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_all_validators_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        state: StateSelector,
+    ) -> Result<Vec<Validator>> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_all_validators(state);
+
+        match result {
+            Ok(validators) => Result::Ok(validators),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_voter_of_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        address: Address,
+        state: StateSelector,
+    ) -> Result<Option<Vote>> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_voter_of(address, state);
+
+        match result {
+            Ok(vote) => Result::Ok(vote),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_registered_validators_count_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        state: StateSelector,
+    ) -> Result<U256> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_registered_validators_count(state);
+
+        match result {
+            Ok(count) => Result::Ok(count),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_validator_set_at_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        height: u64,
+    ) -> Result<Option<ValidatorSet>> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_validator_set_at(height);
+
+        match result {
+            Ok(validator_set) => Result::Ok(validator_set),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
+    async fn get_receipt_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        height: u64,
+        tx_hash: B256,
+    ) -> Result<Option<TxReceipt>> {
+        let mut lock = evm.lock().await;
+        let result = lock.get_receipt(height, tx_hash);
+
+        match result {
+            Ok(receipt) => Result::Ok(receipt),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
     async fn get_account_info_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         address: Address,
+        state: StateSelector,
     ) -> Result<AccountInfo> {
         let mut lock = evm.lock().await;
-        let result = lock.get_account_info(address);
+        let result = lock.get_account_info(address, state);
 
         match result {
             Ok(account) => Result::Ok(account),
@@ -770,12 +1409,12 @@ impl JsEvmWrapper {
     async fn initialize_genesis_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         genesis_ctx: GenesisContext,
-    ) -> Result<()> {
+    ) -> Result<String> {
         let mut lock = evm.lock().await;
         let result = lock.initialize_genesis(genesis_ctx);
 
         match result {
-            Ok(_) => Result::Ok(()),
+            Ok(result) => Result::Ok(result),
             Err(err) => Result::Err(serde::de::Error::custom(err)),
         }
     }
@@ -796,12 +1435,12 @@ impl JsEvmWrapper {
     async fn calculate_top_validators_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         ctx: CalculateTopValidatorsContext,
-    ) -> Result<()> {
+    ) -> Result<Vec<Validator>> {
         let mut lock = evm.lock().await;
         let result = lock.calculate_top_validators(ctx);
 
         match result {
-            Ok(_) => Result::Ok(()),
+            Ok(validators) => Result::Ok(validators),
             Err(err) => Result::Err(serde::de::Error::custom(err)),
         }
     }
@@ -819,12 +1458,26 @@ impl JsEvmWrapper {
         }
     }
 
+    async fn apply_penalties_async(
+        evm: Arc<tokio::sync::Mutex<EvmInner>>,
+        ctx: ApplyPenaltiesContext,
+    ) -> Result<()> {
+        let mut lock = evm.lock().await;
+        let result = lock.apply_penalties(ctx);
+
+        match result {
+            Ok(_) => Result::Ok(()),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+
     async fn code_at_async(
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         address: Address,
+        state: StateSelector,
     ) -> Result<String> {
         let mut lock = evm.lock().await;
-        let result = lock.code_at(address);
+        let result = lock.code_at(address, state);
 
         match result {
             Ok(code) => Result::Ok(revm::primitives::hex::encode_prefixed(code.as_ref())),
@@ -836,9 +1489,10 @@ impl JsEvmWrapper {
         evm: Arc<tokio::sync::Mutex<EvmInner>>,
         address: Address,
         slot: U256,
+        state: StateSelector,
     ) -> Result<String> {
         let mut lock = evm.lock().await;
-        let result = lock.storage_at(address, slot);
+        let result = lock.storage_at(address, slot, state);
 
         match result {
             Ok(slot) => Result::Ok(revm::primitives::hex::encode_prefixed(
@@ -856,9 +1510,7 @@ impl JsEvmWrapper {
         let result = lock.commit(commit_key);
 
         match result {
-            Ok(result) => Result::Ok(CommitResult {
-                dirty_accounts: result,
-            }),
+            Ok(result) => Result::Ok(result),
             Err(err) => Result::Err(serde::de::Error::custom(err)),
         }
     }
@@ -876,4 +1528,598 @@ impl JsEvmWrapper {
             Err(err) => Result::Err(serde::de::Error::custom(err)),
         }
     }
+
+    async fn stats_async(evm: Arc<tokio::sync::Mutex<EvmInner>>) -> Result<StatsResult> {
+        let mut lock = evm.lock().await;
+        let result = lock.stats();
+
+        match result {
+            Ok(result) => Result::Ok(result),
+            Err(err) => Result::Err(serde::de::Error::custom(err)),
+        }
+    }
+}
+
+#[test]
+fn test_process_batch_is_atomic() {
+    use revm::primitives::{address, b256, InvalidTransaction};
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut evm = EvmInner::new(path.path().to_path_buf(), Default::default());
+
+    let caller = address!("00000000000000000000000000000000000000a1");
+    let recipient = address!("00000000000000000000000000000000000000b1");
+
+    let block_context = BlockContext {
+        commit_key: CommitKey(0, 0),
+        gas_limit: U256::from(30_000_000),
+        ..Default::default()
+    };
+    evm.prepare_next_commit(PrepareNextCommitContext {
+        commit_key: block_context.commit_key,
+        randomness: None,
+    })
+    .expect("prepare");
+    state_commit::apply_rewards(
+        &mut evm.persistent_db,
+        evm.pending_commit.as_mut().expect("pending"),
+        [(caller, 1_000_000)].into(),
+    )
+    .expect("rewards");
+
+    let transfer = |nonce: u64, value: u64, tx_hash: B256| TxContext {
+        caller,
+        recipient: Some(recipient),
+        gas_limit: 21_000,
+        gas_price: None,
+        value: U256::from(value),
+        nonce,
+        data: Bytes::new(),
+        tx_hash,
+        block_context: block_context.clone(),
+        spec_id: SpecId::SHANGHAI,
+    };
+    let batch = |transactions: &[TxContext]| ProcessBatchContext {
+        block_context: block_context.clone(),
+        spec_id: SpecId::SHANGHAI,
+        transactions: ctx::encode_batch(transactions),
+        block_reward: None,
+        rewards: Default::default(),
+        current_hash: None,
+    };
+
+    let first = transfer(
+        0,
+        100,
+        b256!("0000000000000000000000000000000000000000000000000000000000000001"),
+    );
+    let second = transfer(
+        1,
+        100,
+        b256!("0000000000000000000000000000000000000000000000000000000000000002"),
+    );
+    let nonce_too_high = transfer(
+        5,
+        100,
+        b256!("0000000000000000000000000000000000000000000000000000000000000003"),
+    );
+
+    // the failing transaction discards the one before it
+    assert!(evm
+        .process_batch(batch(&[first.clone(), nonce_too_high.clone()]))
+        .is_err());
+    let pending = evm.pending_commit.as_ref().expect("pending");
+    assert!(pending.results.is_empty());
+    assert_eq!(
+        evm.get_account_info(caller, StateSelector::Pending)
+            .expect("account")
+            .nonce,
+        0
+    );
+    assert_eq!(
+        evm.get_account_info(recipient, StateSelector::Pending)
+            .expect("account")
+            .balance,
+        U256::ZERO
+    );
+
+    let result = evm.process_batch(batch(&[first, second])).expect("batch");
+    assert_eq!(result.receipts.len(), 2);
+    assert!(result.receipts.iter().all(|receipt| receipt.success));
+    assert_eq!(
+        evm.get_account_info(recipient, StateSelector::Pending)
+            .expect("account")
+            .balance,
+        U256::from(200)
+    );
+
+    // rejected transactions are reported as errors and leave the pending state intact
+    assert!(matches!(
+        evm.process(nonce_too_high),
+        Err(EVMError::Transaction(
+            InvalidTransaction::NonceTooHigh { .. }
+        ))
+    ));
+    assert!(matches!(
+        evm.process(transfer(
+            2,
+            10_000_000,
+            b256!("0000000000000000000000000000000000000000000000000000000000000004"),
+        )),
+        Err(EVMError::Transaction(
+            InvalidTransaction::LackOfFundForMaxFee { .. }
+        ))
+    ));
+    assert_eq!(
+        evm.get_account_info(caller, StateSelector::Pending)
+            .expect("account")
+            .nonce,
+        2
+    );
+    assert_eq!(
+        evm.pending_commit.as_ref().expect("pending").results.len(),
+        2
+    );
+}
+
+#[test]
+fn test_process_batch_parity() {
+    use revm::primitives::{address, b256, bytes, keccak256};
+    use state_commit::{FeeDistribution, GenesisAccount};
+
+    let account = |i: u64| Address::left_padding_from(&(0x1000 + i).to_be_bytes());
+    let treasury = address!("00000000000000000000000000000000000000aa");
+    let proposer = address!("00000000000000000000000000000000000000cb");
+    let randomness = b256!("00000000000000000000000000000000000000000000000000000000000000ee");
+
+    // sstore(0, sload(0) + 1)
+    let counter = address!("00000000000000000000000000000000000000c1");
+    // sstore(0, prevrandao) and sstore(1, IConsensusData(0x0a03).round())
+    let context = address!("00000000000000000000000000000000000000c2");
+    let context_code = [
+        &[0x63][..],
+        &keccak256("round()")[..4],
+        &bytes!("60e01b6000526020600060046000610a035afa506000516001554460005500"),
+    ]
+    .concat();
+
+    let block_context = BlockContext {
+        commit_key: CommitKey(1, 2),
+        gas_limit: U256::from(30_000_000),
+        validator_address: proposer,
+        active_validators: vec![proposer, account(99)],
+        randomness: Some(randomness),
+        fee_distribution: FeeDistribution::new(5_000, 2_000, treasury),
+        ..Default::default()
+    };
+
+    let mut transactions = vec![];
+    let mut nonces = [0u64; 8];
+    let mut push = |from: usize, to: Address, value: u64| {
+        let nonce = nonces[from];
+        nonces[from] += 1;
+        transactions.push(TxContext {
+            caller: account(from as u64),
+            recipient: Some(to),
+            gas_limit: 100_000,
+            gas_price: Some(U256::from(7)),
+            value: U256::from(value),
+            nonce,
+            data: Bytes::new(),
+            tx_hash: keccak256([account(from as u64).as_slice(), &nonce.to_be_bytes()].concat()),
+            block_context: block_context.clone(),
+            spec_id: SpecId::SHANGHAI,
+        });
+    };
+    for i in 0..8 {
+        push(i, counter, 0);
+        push(i, account(8 + i as u64), 1_000);
+        push((i + 1) % 8, account(i as u64), 500);
+    }
+    push(0, context, 0);
+    push(3, proposer, 100);
+
+    let run = |mode: &str| {
+        let path = tempfile::Builder::new()
+            .prefix("evm.mdb")
+            .tempdir()
+            .unwrap();
+        let mut options = EvmOptions {
+            parallel_execution: mode == "parallel",
+            ..Default::default()
+        };
+        options.precompiles.register_consensus_data(0);
+        let mut evm = EvmInner::new(path.path().to_path_buf(), options);
+
+        let mut alloc = (0..8)
+            .map(|i| {
+                (
+                    account(i),
+                    GenesisAccount {
+                        balance: U256::from(1_000_000_000_000_000_000u128),
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect::<std::collections::BTreeMap<_, _>>();
+        alloc.insert(
+            counter,
+            GenesisAccount {
+                code: Some(bytes!("60005460010160005500")),
+                ..Default::default()
+            },
+        );
+        alloc.insert(
+            context,
+            GenesisAccount {
+                code: Some(context_code.clone().into()),
+                ..Default::default()
+            },
+        );
+        let mut pending = PendingCommit::new(CommitKey(0, 0));
+        state_commit::apply_genesis_alloc(&mut evm.persistent_db, &mut pending, alloc)
+            .expect("alloc");
+        evm.pending_commit = Some(pending);
+        evm.commit(CommitKey(0, 0)).expect("commit");
+
+        evm.prepare_next_commit(PrepareNextCommitContext {
+            commit_key: block_context.commit_key,
+            randomness: None,
+        })
+        .expect("prepare");
+
+        let receipts = if mode == "process" {
+            let receipts = transactions
+                .iter()
+                .map(|tx_ctx| evm.process(tx_ctx.clone()).expect("process"))
+                .collect::<Vec<_>>();
+            state_commit::distribute_fees(
+                &mut evm.persistent_db,
+                evm.pending_commit.as_mut().expect("pending"),
+                proposer,
+            )
+            .expect("fees");
+            receipts
+        } else {
+            evm.process_batch(ProcessBatchContext {
+                block_context: block_context.clone(),
+                spec_id: SpecId::SHANGHAI,
+                transactions: ctx::encode_batch(&transactions),
+                block_reward: None,
+                rewards: Default::default(),
+                current_hash: None,
+            })
+            .expect("batch")
+            .receipts
+        };
+
+        // the block context reaches the contracts
+        assert_eq!(
+            evm.storage_at(context, U256::ZERO, StateSelector::Pending)
+                .expect("slot"),
+            U256::from_be_bytes(randomness.0)
+        );
+        assert_eq!(
+            evm.storage_at(context, U256::from(1), StateSelector::Pending)
+                .expect("slot"),
+            U256::from(2)
+        );
+
+        let distributed_fees = evm
+            .pending_commit
+            .as_ref()
+            .and_then(|pending| pending.distributed_fees)
+            .expect("fees");
+        // fees are withheld from the coinbase, which only receives the transfer and its share
+        assert_eq!(
+            evm.get_account_info(proposer, StateSelector::Pending)
+                .expect("account")
+                .balance,
+            U256::from(100) + distributed_fees.validator
+        );
+
+        (
+            serde_json::to_value(&receipts).expect("json"),
+            distributed_fees,
+            evm.state_hash(block_context.commit_key, B256::ZERO)
+                .expect("state hash"),
+        )
+    };
+
+    let expected = run("process");
+    assert_eq!(run("sequential"), expected);
+    assert_eq!(run("parallel"), expected);
+}
+
+#[test]
+fn test_system_call_commit_key_mismatch() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut evm = EvmInner::new(path.path().to_path_buf(), Default::default());
+
+    let top_validators = |commit_key| CalculateTopValidatorsContext {
+        commit_key,
+        timestamp: U256::ZERO,
+        active_validators: 53,
+        validator_address: Address::ZERO,
+        spec_id: SpecId::SHANGHAI,
+    };
+    let rewards_and_votes = |commit_key| UpdateRewardsAndVotesContext {
+        commit_key,
+        timestamp: U256::ZERO,
+        block_reward: 0,
+        validator_address: Address::ZERO,
+        spec_id: SpecId::SHANGHAI,
+        rewards: Default::default(),
+    };
+
+    // no pending commit
+    assert!(matches!(
+        evm.calculate_top_validators(top_validators(CommitKey(1, 0))),
+        Err(EVMError::Database(_))
+    ));
+
+    evm.prepare_next_commit(PrepareNextCommitContext {
+        commit_key: CommitKey(1, 0),
+        randomness: None,
+    })
+    .expect("prepare");
+
+    assert!(matches!(
+        evm.calculate_top_validators(top_validators(CommitKey(1, 1))),
+        Err(EVMError::Database(_))
+    ));
+    assert!(matches!(
+        evm.update_rewards_and_votes(rewards_and_votes(CommitKey(2, 0))),
+        Err(EVMError::Database(_))
+    ));
+    assert_eq!(
+        evm.pending_commit.as_ref().map(|c| c.key),
+        Some(CommitKey(1, 0))
+    );
+}
+
+#[test]
+fn test_genesis_deployer_flow() {
+    use std::collections::BTreeMap;
+
+    use revm::primitives::{address, b256};
+    use state_commit::GenesisAccount;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut evm = EvmInner::new(path.path().to_path_buf(), Default::default());
+
+    let deployer = address!("0000000000000000000000000000000000000001");
+    let validator_contract = deployer.create(0);
+    let account = address!("00000000000000000000000000000000000000a1");
+    let recipient = address!("00000000000000000000000000000000000000b1");
+
+    let genesis_hash = evm
+        .initialize_genesis(GenesisContext {
+            deployer_account: deployer,
+            validator_contract,
+            alloc: BTreeMap::from([(
+                account,
+                GenesisAccount {
+                    balance: U256::from(1_000_000),
+                    ..Default::default()
+                },
+            )]),
+        })
+        .expect("genesis");
+    assert!(!evm.persistent_db.is_height_committed(0));
+
+    let tx = |commit_key, caller, recipient, data: Bytes, value, tx_hash| TxContext {
+        caller,
+        recipient,
+        gas_limit: 10_000_000,
+        gas_price: None,
+        value: U256::from(value),
+        nonce: 0,
+        data,
+        tx_hash,
+        block_context: BlockContext {
+            commit_key,
+            gas_limit: U256::from(30_000_000),
+            ..Default::default()
+        },
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    // the consensus contract is deployed under a commit key no block uses
+    let artifact: serde_json::Value = serde_json::from_str(include_str!(
+        "../../../evm-contracts/source/abis/Consensus.json"
+    ))
+    .expect("artifact");
+    let bytecode: Bytes = artifact["bytecode"]["object"]
+        .as_str()
+        .expect("bytecode")
+        .parse()
+        .expect("hex");
+
+    let deploy_key = CommitKey(2u64.pow(32) + 1, 0);
+    let receipt = evm
+        .process(tx(
+            deploy_key,
+            deployer,
+            None,
+            bytecode,
+            0,
+            b256!("0000000000000000000000000000000000000000000000000000000000000001"),
+        ))
+        .expect("deploy");
+    assert!(receipt.success);
+    assert_eq!(
+        receipt.deployed_contract_address,
+        Some(validator_contract.to_checksum(None))
+    );
+    evm.commit(deploy_key).expect("commit");
+
+    // the genesis allocation is not part of other commits
+    assert_eq!(
+        evm.get_account_info(account, StateSelector::Committed)
+            .expect("account")
+            .balance,
+        U256::ZERO
+    );
+
+    // the genesis block is executed on top of the allocation
+    let genesis_key = CommitKey(0, 0);
+    evm.prepare_next_commit(PrepareNextCommitContext {
+        commit_key: genesis_key,
+        randomness: None,
+    })
+    .expect("prepare");
+    assert_eq!(
+        evm.state_hash(genesis_key, B256::ZERO).expect("state hash"),
+        genesis_hash
+    );
+
+    let receipt = evm
+        .process(tx(
+            genesis_key,
+            account,
+            Some(recipient),
+            Bytes::new(),
+            100,
+            b256!("0000000000000000000000000000000000000000000000000000000000000002"),
+        ))
+        .expect("transfer");
+    assert!(receipt.success);
+    evm.commit(genesis_key).expect("commit");
+
+    assert!(evm.genesis_commit.is_none());
+    assert!(evm.persistent_db.has_voter_index().expect("voter index"));
+    for (address, balance) in [(account, 1_000_000 - 100), (recipient, 100)] {
+        assert_eq!(
+            evm.get_account_info(address, StateSelector::Committed)
+                .expect("account")
+                .balance,
+            U256::from(balance)
+        );
+    }
+
+    // once committed, the genesis reports the hash of the commit and is not prepared again
+    let committed_hash = evm
+        .initialize_genesis(GenesisContext {
+            deployer_account: deployer,
+            validator_contract,
+            alloc: Default::default(),
+        })
+        .expect("genesis");
+    assert_ne!(committed_hash, genesis_hash);
+    assert_eq!(
+        committed_hash,
+        evm.state_hash(genesis_key, B256::ZERO).expect("state hash")
+    );
+    assert!(evm.genesis_commit.is_none());
+}
+
+#[test]
+fn test_process_batch_replay() {
+    use mainsail_evm_core::pruning::PruningMode;
+    use revm::primitives::address;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut evm = EvmInner::new(path.path().to_path_buf(), Default::default());
+
+    let caller = address!("00000000000000000000000000000000000000a1");
+    let recipient = address!("00000000000000000000000000000000000000b1");
+
+    let block_context = |commit_key| BlockContext {
+        commit_key,
+        gas_limit: U256::from(30_000_000),
+        ..Default::default()
+    };
+    let transfer = |commit_key, nonce: u64| TxContext {
+        caller,
+        recipient: Some(recipient),
+        gas_limit: 21_000,
+        gas_price: None,
+        value: U256::from(100),
+        nonce,
+        data: Bytes::new(),
+        tx_hash: B256::with_last_byte(nonce as u8 + 1),
+        block_context: block_context(commit_key),
+        spec_id: SpecId::SHANGHAI,
+    };
+    let batch = |commit_key, nonce, block_reward| ProcessBatchContext {
+        block_context: block_context(commit_key),
+        spec_id: SpecId::SHANGHAI,
+        transactions: ctx::encode_batch(&[
+            transfer(commit_key, nonce),
+            transfer(commit_key, nonce + 1),
+        ]),
+        block_reward,
+        rewards: Default::default(),
+        current_hash: Some(B256::ZERO),
+    };
+
+    let commit_key = CommitKey(1, 0);
+    evm.prepare_next_commit(PrepareNextCommitContext {
+        commit_key,
+        randomness: None,
+    })
+    .expect("prepare");
+    state_commit::apply_rewards(
+        &mut evm.persistent_db,
+        evm.pending_commit.as_mut().expect("pending"),
+        [(caller, 1_000_000)].into(),
+    )
+    .expect("rewards");
+
+    let processed = evm
+        .process_batch(batch(commit_key, 0, None))
+        .expect("batch");
+    evm.commit(commit_key).expect("commit");
+
+    // a pending commit of the next height is left alone by the replay
+    let next_key = CommitKey(2, 0);
+    evm.prepare_next_commit(PrepareNextCommitContext {
+        commit_key: next_key,
+        randomness: None,
+    })
+    .expect("prepare");
+
+    // rewards of the replayed block are not applied again
+    let replayed = evm
+        .process_batch(batch(commit_key, 0, Some(1_000)))
+        .expect("replay");
+    assert_eq!(
+        serde_json::to_value(&replayed.receipts).expect("json"),
+        serde_json::to_value(&processed.receipts).expect("json")
+    );
+    assert_eq!(replayed.state_hash, processed.state_hash);
+    assert_eq!(
+        evm.pending_commit.as_ref().map(|pending| pending.key),
+        Some(next_key)
+    );
+    assert!(evm
+        .pending_commit
+        .as_ref()
+        .expect("pending")
+        .cache
+        .accounts
+        .is_empty());
+
+    // pruned receipts cannot be replayed
+    evm.persistent_db.set_pruning_mode(PruningMode::HashesOnly);
+    evm.pending_commit = None;
+    evm.process_batch(batch(next_key, 2, None)).expect("batch");
+    evm.commit(next_key).expect("commit");
+    assert!(matches!(
+        evm.process_batch(batch(next_key, 2, Some(1_000))),
+        Err(EVMError::Database(_))
+    ));
 }
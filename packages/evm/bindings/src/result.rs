@@ -1,9 +1,16 @@
-use mainsail_evm_core::{receipt::TxReceipt, state_changes::AccountUpdate};
+use mainsail_evm_core::{
+    db::{DbStats, PendingCommitStats, TableStats, ValidatorSet, ValidatorSetEntry},
+    receipt::TxReceipt,
+    state_changes::AccountUpdate,
+    state_commit::{AppliedPenalty, DistributedFees, Reward},
+    system_call::{Validator, Vote},
+    validation::TxValidation,
+};
 use napi::{JsBigInt, JsBuffer, JsString};
 use napi_derive::napi;
-use revm::primitives::{AccountInfo, Bytes};
+use revm::primitives::{hex::ToHexExt, AccountInfo, Bytes, B256, U256};
 
-use crate::utils;
+use crate::{ctx::JsReward, utils};
 
 #[napi(object)]
 pub struct JsProcessResult {
@@ -17,9 +24,42 @@ impl JsProcessResult {
     }
 }
 
+#[napi(object)]
+pub struct JsProcessBatchResult {
+    pub receipts: Vec<JsTransactionReceipt>,
+    pub state_hash: Option<JsString>,
+}
+
+impl JsProcessBatchResult {
+    pub fn new(node_env: &napi::Env, result: ProcessBatchResult) -> anyhow::Result<Self> {
+        let mut receipts = Vec::with_capacity(result.receipts.len());
+        for receipt in result.receipts {
+            receipts.push(JsTransactionReceipt::new(node_env, receipt)?);
+        }
+
+        let state_hash = match result.state_hash {
+            Some(state_hash) => Some(node_env.create_string_from_std(state_hash)?),
+            None => None,
+        };
+
+        Ok(Self {
+            receipts,
+            state_hash,
+        })
+    }
+}
+
 #[napi(object)]
 pub struct JsCommitResult {
     pub dirty_accounts: Vec<JsAccountUpdate>,
+    /// Set if the fees of the commit were distributed
+    pub fees: Option<JsDistributedFees>,
+    /// Rewards credited by consensus, in the order they were applied
+    pub rewards: Vec<JsReward>,
+    /// Penalties deducted by consensus, in the order they were applied
+    pub penalties: Vec<JsAppliedPenalty>,
+    /// Receipts of the system calls, in the order they were executed
+    pub system_receipts: Vec<JsSystemReceipt>,
 }
 
 impl JsCommitResult {
@@ -29,7 +69,86 @@ impl JsCommitResult {
             dirty_accounts.push(JsAccountUpdate::new(node_env, item)?);
         }
 
-        Ok(Self { dirty_accounts })
+        let fees = match result.fees {
+            Some(fees) => Some(JsDistributedFees::new(node_env, fees)?),
+            None => None,
+        };
+
+        let mut rewards = Vec::with_capacity(result.rewards.len());
+        for reward in result.rewards {
+            rewards.push(JsReward {
+                address: node_env.create_string_from_std(reward.address.to_checksum(None))?,
+                amount: utils::convert_u256_to_bigint(node_env, U256::from(reward.amount))?,
+                kind: node_env.create_string_from_std(reward.kind)?,
+            });
+        }
+
+        let mut penalties = Vec::with_capacity(result.penalties.len());
+        for penalty in result.penalties {
+            penalties.push(JsAppliedPenalty::new(node_env, penalty)?);
+        }
+
+        let mut system_receipts = Vec::with_capacity(result.system_receipts.len());
+        for (tx_hash, receipt) in result.system_receipts {
+            system_receipts.push(JsSystemReceipt {
+                tx_hash: node_env.create_string_from_std(tx_hash.encode_hex())?,
+                receipt: JsTransactionReceipt::new(node_env, receipt)?,
+            });
+        }
+
+        Ok(Self {
+            dirty_accounts,
+            fees,
+            rewards,
+            penalties,
+            system_receipts,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsSystemReceipt {
+    /// Synthetic hash the receipt can be looked up by
+    pub tx_hash: JsString,
+    pub receipt: JsTransactionReceipt,
+}
+
+#[napi(object)]
+pub struct JsAppliedPenalty {
+    pub address: JsString,
+    pub reason: JsString,
+    pub requested: JsBigInt,
+    /// Less than `requested` if the balance was insufficient
+    pub deducted: JsBigInt,
+}
+
+impl JsAppliedPenalty {
+    pub fn new(node_env: &napi::Env, penalty: AppliedPenalty) -> anyhow::Result<Self> {
+        Ok(Self {
+            address: node_env.create_string_from_std(penalty.address.to_checksum(None))?,
+            reason: node_env.create_string_from_std(penalty.reason)?,
+            requested: utils::convert_u256_to_bigint(node_env, penalty.requested)?,
+            deducted: utils::convert_u256_to_bigint(node_env, penalty.deducted)?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsDistributedFees {
+    pub collected: JsBigInt,
+    pub burned: JsBigInt,
+    pub treasury: JsBigInt,
+    pub validator: JsBigInt,
+}
+
+impl JsDistributedFees {
+    pub fn new(node_env: &napi::Env, fees: DistributedFees) -> anyhow::Result<Self> {
+        Ok(Self {
+            collected: utils::convert_u256_to_bigint(node_env, fees.collected)?,
+            burned: utils::convert_u256_to_bigint(node_env, fees.burned)?,
+            treasury: utils::convert_u256_to_bigint(node_env, fees.treasury)?,
+            validator: utils::convert_u256_to_bigint(node_env, fees.validator)?,
+        })
     }
 }
 
@@ -52,6 +171,34 @@ impl JsViewResult {
     }
 }
 
+#[napi(object)]
+pub struct JsValidationResult {
+    pub valid: bool,
+    /// Reason the transaction would be rejected
+    pub error: Option<JsString>,
+    pub intrinsic_gas: JsBigInt,
+    /// Nonce the transaction is expected to have
+    pub nonce: JsBigInt,
+    pub balance: JsBigInt,
+}
+
+impl JsValidationResult {
+    pub fn new(node_env: &napi::Env, result: TxValidation) -> anyhow::Result<Self> {
+        let error = match &result.error {
+            Some(error) => Some(node_env.create_string_from_std(error.to_string())?),
+            None => None,
+        };
+
+        Ok(Self {
+            valid: result.is_valid(),
+            error,
+            intrinsic_gas: node_env.create_bigint_from_u64(result.intrinsic_gas)?,
+            nonce: node_env.create_bigint_from_u64(result.nonce)?,
+            balance: utils::convert_u256_to_bigint(node_env, result.balance)?,
+        })
+    }
+}
+
 #[napi(object)]
 pub struct JsTransactionReceipt {
     pub gas_used: JsBigInt,
@@ -62,11 +209,24 @@ pub struct JsTransactionReceipt {
     // TODO: typing
     pub logs: serde_json::Value,
     pub output: Option<JsBuffer>,
+    /// Index of the transaction in its commit
+    pub tx_index: JsBigInt,
+    /// Gas used by the transaction and all transactions before it in the same commit
+    pub cumulative_gas_used: JsBigInt,
 }
 
 #[derive(Default)]
 pub struct CommitResult {
     pub dirty_accounts: Vec<AccountUpdate>,
+    pub fees: Option<DistributedFees>,
+    pub rewards: Vec<Reward>,
+    pub penalties: Vec<AppliedPenalty>,
+    pub system_receipts: Vec<(B256, TxReceipt)>,
+}
+
+pub struct ProcessBatchResult {
+    pub receipts: Vec<TxReceipt>,
+    pub state_hash: Option<String>,
 }
 
 pub struct TxViewResult {
@@ -74,6 +234,11 @@ pub struct TxViewResult {
     pub output: Option<Bytes>,
 }
 
+pub struct StatsResult {
+    pub db: DbStats,
+    pub pending: Option<PendingCommitStats>,
+}
+
 impl JsTransactionReceipt {
     pub fn new(node_env: &napi::Env, receipt: TxReceipt) -> anyhow::Result<Self> {
         let deployed_contract_address =
@@ -98,6 +263,8 @@ impl JsTransactionReceipt {
                     .unwrap()
                     .into_raw()
             }),
+            tx_index: node_env.create_bigint_from_u64(receipt.tx_index)?,
+            cumulative_gas_used: node_env.create_bigint_from_u64(receipt.cumulative_gas_used)?,
         })
     }
 }
@@ -117,6 +284,89 @@ impl JsAccountInfo {
     }
 }
 
+#[napi(object)]
+pub struct JsValidator {
+    pub address: JsString,
+    /// Hex encoded BLS12-381 public key
+    pub bls_public_key: JsString,
+    pub vote_balance: JsBigInt,
+    pub voters_count: JsBigInt,
+    pub is_resigned: bool,
+}
+
+impl JsValidator {
+    pub fn new(node_env: &napi::Env, validator: Validator) -> anyhow::Result<Self> {
+        Ok(JsValidator {
+            address: node_env.create_string_from_std(validator.addr.to_checksum(None))?,
+            bls_public_key: node_env
+                .create_string_from_std(validator.data.bls12_381_public_key.encode_hex())?,
+            vote_balance: utils::convert_u256_to_bigint(node_env, validator.data.voteBalance)?,
+            voters_count: utils::convert_u256_to_bigint(node_env, validator.data.votersCount)?,
+            is_resigned: validator.data.isResigned,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsValidatorSet {
+    /// Height of the commit the set was calculated in
+    pub height: JsBigInt,
+    pub round: JsBigInt,
+    pub validators: Vec<JsValidatorSetEntry>,
+}
+
+impl JsValidatorSet {
+    pub fn new(node_env: &napi::Env, validator_set: ValidatorSet) -> anyhow::Result<Self> {
+        let mut validators = Vec::with_capacity(validator_set.validators.len());
+        for validator in validator_set.validators {
+            validators.push(JsValidatorSetEntry::new(node_env, validator)?);
+        }
+
+        Ok(JsValidatorSet {
+            height: node_env.create_bigint_from_u64(validator_set.height)?,
+            round: node_env.create_bigint_from_u64(validator_set.round)?,
+            validators,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsValidatorSetEntry {
+    pub address: JsString,
+    /// Hex encoded BLS12-381 public key
+    pub bls_public_key: JsString,
+    pub vote_balance: JsBigInt,
+    pub voters_count: JsBigInt,
+}
+
+impl JsValidatorSetEntry {
+    pub fn new(node_env: &napi::Env, validator: ValidatorSetEntry) -> anyhow::Result<Self> {
+        Ok(JsValidatorSetEntry {
+            address: node_env.create_string_from_std(validator.address.to_checksum(None))?,
+            bls_public_key: node_env
+                .create_string_from_std(validator.bls_public_key.encode_hex())?,
+            vote_balance: utils::convert_u256_to_bigint(node_env, validator.vote_balance)?,
+            voters_count: utils::convert_u256_to_bigint(node_env, validator.voters_count)?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsVote {
+    /// Validator the vote is for
+    pub validator: JsString,
+    pub balance: JsBigInt,
+}
+
+impl JsVote {
+    pub fn new(node_env: &napi::Env, vote: Vote) -> anyhow::Result<Self> {
+        Ok(JsVote {
+            validator: node_env.create_string_from_std(vote.validator.to_checksum(None))?,
+            balance: utils::convert_u256_to_bigint(node_env, vote.balance)?,
+        })
+    }
+}
+
 #[napi(object)]
 pub struct JsAccountUpdate {
     pub address: JsString,
@@ -147,3 +397,89 @@ impl JsAccountUpdate {
         })
     }
 }
+
+#[napi(object)]
+pub struct JsTableStats {
+    pub name: JsString,
+    pub entries: JsBigInt,
+    pub depth: u32,
+    pub branch_pages: JsBigInt,
+    pub leaf_pages: JsBigInt,
+    pub overflow_pages: JsBigInt,
+}
+
+impl JsTableStats {
+    pub fn new(node_env: &napi::Env, stats: TableStats) -> anyhow::Result<Self> {
+        Ok(JsTableStats {
+            name: node_env.create_string(stats.name)?,
+            entries: node_env.create_bigint_from_u64(stats.entries)?,
+            depth: stats.depth,
+            branch_pages: node_env.create_bigint_from_u64(stats.branch_pages)?,
+            leaf_pages: node_env.create_bigint_from_u64(stats.leaf_pages)?,
+            overflow_pages: node_env.create_bigint_from_u64(stats.overflow_pages)?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsPendingCommitStats {
+    pub accounts: JsBigInt,
+    pub storage_slots: JsBigInt,
+    pub transactions: JsBigInt,
+}
+
+impl JsPendingCommitStats {
+    pub fn new(node_env: &napi::Env, stats: PendingCommitStats) -> anyhow::Result<Self> {
+        Ok(JsPendingCommitStats {
+            accounts: node_env.create_bigint_from_u64(stats.accounts)?,
+            storage_slots: node_env.create_bigint_from_u64(stats.storage_slots)?,
+            transactions: node_env.create_bigint_from_u64(stats.transactions)?,
+        })
+    }
+}
+
+#[napi(object)]
+pub struct JsStats {
+    pub tables: Vec<JsTableStats>,
+    pub page_size: u32,
+    pub map_size: JsBigInt,
+    pub real_disk_size: JsBigInt,
+    pub last_committed_height: Option<JsBigInt>,
+    pub resizes: JsBigInt,
+    pub pruned_heights: JsBigInt,
+    pub pruned_receipts: JsBigInt,
+    pub pending_commit: Option<JsPendingCommitStats>,
+}
+
+impl JsStats {
+    pub fn new(node_env: &napi::Env, result: StatsResult) -> anyhow::Result<Self> {
+        let StatsResult { db, pending } = result;
+
+        let mut tables = Vec::with_capacity(db.tables.len());
+        for table in db.tables {
+            tables.push(JsTableStats::new(node_env, table)?);
+        }
+
+        let last_committed_height = match db.last_committed_height {
+            Some(height) => Some(node_env.create_bigint_from_u64(height)?),
+            None => None,
+        };
+
+        let pending_commit = match pending {
+            Some(pending) => Some(JsPendingCommitStats::new(node_env, pending)?),
+            None => None,
+        };
+
+        Ok(JsStats {
+            tables,
+            page_size: db.page_size,
+            map_size: node_env.create_bigint_from_u64(db.map_size)?,
+            real_disk_size: node_env.create_bigint_from_u64(db.real_disk_size)?,
+            last_committed_height,
+            resizes: node_env.create_bigint_from_u64(db.resizes)?,
+            pruned_heights: node_env.create_bigint_from_u64(db.total_pruned.heights)?,
+            pruned_receipts: node_env.create_bigint_from_u64(db.total_pruned.receipts)?,
+            pending_commit,
+        })
+    }
+}
@@ -0,0 +1,262 @@
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter},
+    path::PathBuf,
+    process::ExitCode,
+};
+
+use anyhow::Context;
+use clap::{Parser, Subcommand};
+use mainsail_evm_core::{db::PersistentDB, inspect::StateDump};
+use revm::{
+    primitives::{AccountInfo, Address, B256},
+    DatabaseRef,
+};
+use serde::Serialize;
+
+/// Inspect and maintain a Mainsail EVM database without starting a node.
+#[derive(Parser)]
+#[command(name = "mainsail-evm", version)]
+struct Cli {
+    /// Directory containing `evm.mdb`
+    #[arg(long, short)]
+    path: PathBuf,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Print balance, nonce and code hash of an account
+    Account { address: Address },
+    /// Dump all storage slots of a contract
+    Storage { address: Address },
+    /// List committed heights with their state hashes
+    Commits {
+        #[arg(long, default_value_t = 0)]
+        from: u64,
+        #[arg(long, default_value_t = 100)]
+        limit: usize,
+    },
+    /// Show the receipt of a transaction
+    Receipt {
+        tx_hash: B256,
+        /// Height of the transaction, searches all commits if omitted
+        #[arg(long)]
+        height: Option<u64>,
+    },
+    /// Decode every entry and check references between tables
+    Verify,
+    /// Write accounts, contracts and storage to a JSON file
+    Export { file: PathBuf },
+    /// Load accounts, contracts and storage from a JSON file into an empty database
+    Import { file: PathBuf },
+}
+
+fn main() -> anyhow::Result<ExitCode> {
+    run(Cli::parse())
+}
+
+fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    // only an import writes, every other command must leave the database untouched
+    let db = match cli.command {
+        Command::Import { .. } => PersistentDB::new(cli.path.clone()),
+        _ => PersistentDB::open_read_only(cli.path.clone()),
+    }
+    .with_context(|| format!("failed to open {}", cli.path.display()))?;
+
+    let result = execute(&db, cli.command);
+    db.close();
+
+    result
+}
+
+fn execute(db: &PersistentDB, command: Command) -> anyhow::Result<ExitCode> {
+    match command {
+        Command::Account { address } => {
+            print_json(&db.basic_ref(address)?.map(AccountInfo::without_code))?;
+        }
+        Command::Storage { address } => {
+            print_json(&db.storage_entries(address)?)?;
+        }
+        Command::Commits { from, limit } => {
+            print_json(&db.committed_heights(from, limit)?)?;
+        }
+        Command::Receipt { tx_hash, height } => {
+            let receipt = match height {
+                Some(height) => db
+                    .get_committed_receipt(height, tx_hash)?
+                    .1
+                    .map(|receipt| (height, receipt)),
+                None => db.find_receipt(tx_hash)?,
+            };
+
+            match receipt {
+                Some((height, receipt)) => {
+                    #[derive(Serialize)]
+                    struct HeightReceipt<T> {
+                        height: u64,
+                        receipt: T,
+                    }
+
+                    print_json(&HeightReceipt { height, receipt })?
+                }
+                None => {
+                    eprintln!("receipt {tx_hash} not found");
+                    return Ok(ExitCode::FAILURE);
+                }
+            }
+        }
+        Command::Verify => {
+            let report = db.verify()?;
+            print_json(&report)?;
+
+            if !report.errors.is_empty() {
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+        Command::Export { file } => {
+            let dump = db.export_state()?;
+
+            let writer = BufWriter::new(
+                File::create(&file)
+                    .with_context(|| format!("failed to create {}", file.display()))?,
+            );
+            serde_json::to_writer(writer, &dump)?;
+
+            eprintln!(
+                "exported {} accounts, {} contracts and storage of {} accounts",
+                dump.accounts.len(),
+                dump.contracts.len(),
+                dump.storage.len()
+            );
+        }
+        Command::Import { file } => {
+            let reader = BufReader::new(
+                File::open(&file).with_context(|| format!("failed to open {}", file.display()))?,
+            );
+            let dump: StateDump = serde_json::from_reader(reader)?;
+
+            eprintln!(
+                "importing {} accounts, {} contracts and storage of {} accounts",
+                dump.accounts.len(),
+                dump.contracts.len(),
+                dump.storage.len()
+            );
+
+            db.import_state(dump)?;
+        }
+    }
+
+    Ok(ExitCode::SUCCESS)
+}
+
+fn print_json<T: Serialize>(value: &T) -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(value)?);
+    Ok(())
+}
+
+#[cfg(test)]
+fn run_args(path: &std::path::Path, args: &[&str]) -> anyhow::Result<ExitCode> {
+    let path = path.to_str().expect("path");
+    run(Cli::try_parse_from(
+        ["mainsail-evm", "--path", path].iter().chain(args),
+    )?)
+}
+
+#[test]
+fn test_read_only_commands() {
+    use mainsail_evm_core::{
+        db::{CommitKey, PendingCommit},
+        state_commit,
+    };
+    use revm::primitives::address;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    // a missing database is not created
+    let missing = path.path().join("missing");
+    assert!(run_args(&missing, &["verify"]).is_err());
+    assert!(!missing.exists());
+
+    let address = address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+    state_commit::apply_rewards(&mut db, &mut pending, [(address, 100)].into()).expect("rewards");
+    state_commit::commit_to_db(&mut db, pending).expect("commit");
+    db.close();
+
+    let address = address.to_string();
+    for args in [
+        &["account", &address][..],
+        &["storage", &address],
+        &["commits"],
+        &["verify"],
+    ] {
+        assert_eq!(
+            run_args(path.path(), args).expect("command"),
+            ExitCode::SUCCESS
+        );
+    }
+    assert_eq!(
+        run_args(path.path(), &["receipt", &B256::ZERO.to_string()]).expect("receipt"),
+        ExitCode::FAILURE
+    );
+
+    // the database is not opened for writing
+    let db = PersistentDB::open_read_only(path.path().to_path_buf()).expect("read-only");
+    assert!(db.import_state(Default::default()).is_err());
+}
+
+#[test]
+fn test_export_import() {
+    use mainsail_evm_core::{
+        db::{CommitKey, PendingCommit},
+        state_commit,
+    };
+    use revm::primitives::{address, U256};
+
+    let source = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let target = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let file = source.path().join("state.json");
+    let file = file.to_str().expect("path");
+
+    let address = address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    let mut db = PersistentDB::new(source.path().to_path_buf()).expect("database");
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+    state_commit::apply_rewards(&mut db, &mut pending, [(address, 100)].into()).expect("rewards");
+    state_commit::commit_to_db(&mut db, pending).expect("commit");
+    db.close();
+
+    assert_eq!(
+        run_args(source.path(), &["export", file]).expect("export"),
+        ExitCode::SUCCESS
+    );
+    assert_eq!(
+        run_args(target.path(), &["import", file]).expect("import"),
+        ExitCode::SUCCESS
+    );
+    // importing twice is refused
+    assert!(run_args(target.path(), &["import", file]).is_err());
+    assert_eq!(
+        run_args(target.path(), &["verify"]).expect("verify"),
+        ExitCode::SUCCESS
+    );
+
+    let db = PersistentDB::open_read_only(target.path().to_path_buf()).expect("read-only");
+    assert_eq!(
+        db.basic_ref(address).expect("basic").map(|a| a.balance),
+        Some(U256::from(100))
+    );
+    assert!(!db.has_voter_index().expect("voter index"));
+}
@@ -1,4 +1,10 @@
-use std::{borrow::Cow, cell::RefCell, collections::BTreeMap, convert::Infallible, path::PathBuf};
+use std::{
+    borrow::Cow,
+    cell::{Cell, RefCell},
+    collections::BTreeMap,
+    convert::Infallible,
+    path::PathBuf,
+};
 
 use heed::{EnvFlags, EnvOpenOptions};
 use rayon::slice::ParallelSliceMut;
@@ -6,14 +12,16 @@ use revm::{primitives::*, CacheState, Database, DatabaseRef, TransitionState};
 use serde::{Deserialize, Serialize};
 
 use crate::{
-    receipt::{map_execution_result, TxReceipt},
+    migrations::{self, MigrationProgress},
+    pruning::{self, PruneStats, PruningMode},
+    receipt::{map_execution_result, map_system_results, TxPosition, TxReceipt},
     state_changes,
-    state_commit::StateCommit,
-    state_hash,
+    state_commit::{AppliedPenalty, DistributedFees, FeeDistribution, Reward, StateCommit},
+    state_hash, voters,
 };
 
 #[derive(Debug)]
-struct AddressWrapper(Address);
+pub(crate) struct AddressWrapper(pub(crate) Address);
 impl heed::BytesEncode<'_> for AddressWrapper {
     type EItem = AddressWrapper;
 
@@ -31,7 +39,7 @@ impl heed::BytesDecode<'_> for AddressWrapper {
 }
 
 #[derive(Debug)]
-struct ContractWrapper(B256);
+pub(crate) struct ContractWrapper(pub(crate) B256);
 impl heed::BytesEncode<'_> for ContractWrapper {
     type EItem = ContractWrapper;
 
@@ -40,23 +48,45 @@ impl heed::BytesEncode<'_> for ContractWrapper {
     }
 }
 
-type HeedHeight = heed::types::U64<heed::byteorder::LittleEndian>;
+impl heed::BytesDecode<'_> for ContractWrapper {
+    type DItem = ContractWrapper;
+
+    fn bytes_decode(bytes: &'_ [u8]) -> Result<Self::DItem, heed::BoxedError> {
+        Ok(ContractWrapper(B256::from_slice(bytes)))
+    }
+}
+
+type HeedU64 = heed::types::U64<heed::byteorder::LittleEndian>;
+type HeedHeight = HeedU64;
 type StorageEntry = (U256, U256);
 
 // txHash -> receipt
 #[derive(Serialize, Deserialize)]
-struct CommitReceipts {
-    accounts_hash: B256,
-    storage_hash: B256,
-    contracts_hash: B256,
-    tx_receipts: HashMap<B256, TxReceipt>,
+pub(crate) struct CommitReceipts {
+    pub(crate) accounts_hash: B256,
+    pub(crate) storage_hash: B256,
+    pub(crate) contracts_hash: B256,
+    // Randomness provided by consensus, exposed as `block.prevrandao`
+    pub(crate) randomness: B256,
+    // Gas used by all transactions of the commit
+    pub(crate) gas_used: u64,
+    pub(crate) tx_receipts: HashMap<B256, TxReceipt>,
+    // Set once `tx_receipts` have been dropped by pruning
+    pub(crate) pruned: bool,
+    // Penalties deducted by consensus, kept when pruning
+    pub(crate) penalties: Vec<AppliedPenalty>,
 }
 
-struct InnerStorage {
-    accounts: heed::Database<AddressWrapper, heed::types::SerdeBincode<AccountInfo>>,
-    commits: heed::Database<HeedHeight, heed::types::SerdeBincode<CommitReceipts>>,
-    contracts: heed::Database<ContractWrapper, heed::types::SerdeBincode<Bytecode>>,
-    storage: heed::Database<AddressWrapper, heed::types::SerdeBincode<StorageEntry>>,
+#[derive(Clone, Copy)]
+pub(crate) struct InnerStorage {
+    pub(crate) accounts: heed::Database<AddressWrapper, heed::types::SerdeBincode<AccountInfo>>,
+    pub(crate) commits: heed::Database<HeedHeight, heed::types::SerdeBincode<CommitReceipts>>,
+    pub(crate) contracts: heed::Database<ContractWrapper, heed::types::SerdeBincode<Bytecode>>,
+    pub(crate) metadata: heed::Database<heed::types::Str, HeedU64>,
+    pub(crate) storage: heed::Database<AddressWrapper, heed::types::SerdeBincode<StorageEntry>>,
+    pub(crate) validator_sets: heed::Database<HeedHeight, heed::types::SerdeBincode<ValidatorSet>>,
+    // voter -> validator
+    pub(crate) voters: heed::Database<AddressWrapper, heed::types::SerdeBincode<Address>>,
 }
 
 // A (height, round) pair used to associate state with a processable unit.
@@ -69,22 +99,154 @@ pub struct PendingCommit {
     pub cache: CacheState,
     pub results: BTreeMap<B256, ExecutionResult>,
     pub transitions: TransitionState,
+    pub randomness: B256,
+    /// Gas used by the transactions in `results`
+    pub gas_used: u64,
+    pub positions: BTreeMap<B256, TxPosition>,
+    /// Fees withheld from the coinbase until they are distributed, only collected while a
+    /// `fee_distribution` is set
+    pub fees: U256,
+    pub fee_distribution: Option<FeeDistribution>,
+    pub distributed_fees: Option<DistributedFees>,
+    /// Rewards credited by consensus, in the order they were applied
+    pub rewards: Vec<Reward>,
+    /// Penalties deducted by consensus, in the order they were applied
+    pub penalties: Vec<AppliedPenalty>,
+    /// Set if the top validators were calculated in this commit
+    pub validator_set: Option<ValidatorSet>,
+    /// Results of the system calls of the commit by their synthetic hash, in the order they were
+    /// executed, see [`crate::system_call::system_tx_hash`]
+    pub system_results: Vec<(B256, ExecutionResult)>,
+}
+
+/// Top validators calculated by the consensus contract in a commit.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSet {
+    pub height: u64,
+    pub round: u64,
+    /// In the order returned by the consensus contract
+    pub validators: Vec<ValidatorSetEntry>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ValidatorSetEntry {
+    pub address: Address,
+    pub bls_public_key: Bytes,
+    pub vote_balance: U256,
+    pub voters_count: U256,
 }
 
 #[derive(Clone, Debug)]
 pub struct GenesisInfo {
-    pub account: Address,
     pub deployer_account: Address,
     pub validator_contract: Address,
-    pub initial_supply: U256,
 }
 
 pub struct PersistentDB {
-    env: heed::Env,
-    inner: RefCell<InnerStorage>,
+    pub(crate) env: heed::Env,
+    pub(crate) inner: RefCell<InnerStorage>,
+    schema_version: u64,
+    map_size_growth: MapSizeGrowth,
+    max_map_size: Option<usize>,
+    pruning_mode: PruningMode,
+    last_prune: Cell<PruneStats>,
+    resizes: Cell<u64>,
     pub genesis_info: Option<GenesisInfo>,
 }
 
+#[derive(Clone, Debug, Default)]
+pub struct TableStats {
+    pub name: &'static str,
+    pub entries: u64,
+    pub depth: u32,
+    pub branch_pages: u64,
+    pub leaf_pages: u64,
+    pub overflow_pages: u64,
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct DbStats {
+    pub tables: Vec<TableStats>,
+    pub page_size: u32,
+    pub map_size: u64,
+    pub real_disk_size: u64,
+    pub last_committed_height: Option<u64>,
+    // Number of resizes since the database was opened
+    pub resizes: u64,
+    pub last_prune: PruneStats,
+    pub total_pruned: PruneStats,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct PendingCommitStats {
+    pub accounts: u64,
+    pub storage_slots: u64,
+    pub transactions: u64,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SyncMode {
+    /// Flush data and metadata on every commit.
+    #[default]
+    Full,
+    /// Skip flushing metadata, the last commit may be lost on a system crash.
+    NoMetaSync,
+    /// Never flush explicitly, only suitable for throwaway networks.
+    NoSync,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum MapSizeGrowth {
+    /// Grow to the next multiple of the given number of bytes.
+    Step(usize),
+    /// Multiply the current map size by the given factor.
+    Factor(f64),
+}
+
+impl Default for MapSizeGrowth {
+    fn default() -> Self {
+        Self::Step(MAP_SIZE_UNIT)
+    }
+}
+
+impl MapSizeGrowth {
+    pub fn next_map_size(&self, map_size: usize) -> usize {
+        let next_map_size = match *self {
+            MapSizeGrowth::Step(step) => map_size / step * step + step,
+            MapSizeGrowth::Factor(factor) => (map_size as f64 * factor).ceil() as usize,
+        };
+
+        // always grow by at least one aligned chunk, LMDB expects a multiple of the page size
+        next_map_size
+            .max(map_size + 1)
+            .next_multiple_of(MAP_SIZE_ALIGNMENT)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PersistentDBOptions {
+    pub initial_map_size: usize,
+    pub map_size_growth: MapSizeGrowth,
+    // Upper bound for resizes, unlimited if unset
+    pub max_map_size: Option<usize>,
+    pub sync_mode: SyncMode,
+    pub read_ahead: bool,
+    pub pruning_mode: PruningMode,
+}
+
+impl Default for PersistentDBOptions {
+    fn default() -> Self {
+        Self {
+            initial_map_size: MAP_SIZE_UNIT,
+            map_size_growth: Default::default(),
+            max_map_size: None,
+            sync_mode: Default::default(),
+            read_ahead: true,
+            pruning_mode: Default::default(),
+        }
+    }
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
     #[error("IO error")]
@@ -93,31 +255,140 @@ pub enum Error {
     Heed(#[from] heed::Error),
     #[error("db full error")]
     DbFull,
+    #[error("db reached max map size of {0} bytes")]
+    MaxMapSizeReached(usize),
     #[error("bincode error")]
     Bincode(#[from] bincode::Error),
     #[error("infallible error")]
     Infallible(#[from] Infallible),
+    #[error("db is not empty")]
+    NotEmpty,
+    #[error("receipts pruned for height {0}")]
+    ReceiptsPruned(u64),
+    #[error("unsupported schema version {found} (supported up to {supported})")]
+    UnsupportedSchemaVersion { found: u64, supported: u64 },
+    #[error(
+        "schema version {found} must be migrated to {expected} before it can be opened read-only"
+    )]
+    MigrationRequired { found: u64, expected: u64 },
+    #[error("no database found at {0}")]
+    NotFound(PathBuf),
+    #[error("table {0} is missing")]
+    MissingTable(&'static str),
+    #[error("transaction gas limit {gas_limit} exceeds the remaining block gas {remaining}")]
+    BlockGasLimitExceeded { gas_limit: u64, remaining: u64 },
+    #[error("reward overflows the balance of {0}")]
+    RewardOverflow(Address),
+    #[error("balance {balance} of {address} is insufficient for a penalty of {amount}")]
+    InsufficientBalance {
+        address: Address,
+        balance: U256,
+        amount: U256,
+    },
+    #[error("genesis alloc sets storage of the validator contract {0}")]
+    GenesisValidatorStorage(Address),
 }
 
 impl PersistentDB {
     pub fn new(path: PathBuf) -> Result<Self, Error> {
+        Self::new_with_options(path, Default::default())
+    }
+
+    pub fn new_with_options(path: PathBuf, options: PersistentDBOptions) -> Result<Self, Error> {
         std::fs::create_dir_all(&path)?;
 
+        let mut flags = EnvFlags::NO_SUB_DIR;
+        match options.sync_mode {
+            SyncMode::Full => (),
+            SyncMode::NoMetaSync => flags |= EnvFlags::NO_META_SYNC,
+            SyncMode::NoSync => flags |= EnvFlags::NO_SYNC,
+        }
+        if !options.read_ahead {
+            flags |= EnvFlags::NO_READ_AHEAD;
+        }
+
         let mut env_builder = EnvOpenOptions::new();
-        env_builder.max_dbs(4);
-        env_builder.map_size(1 * MAP_SIZE_UNIT);
-        unsafe { env_builder.flags(EnvFlags::NO_SUB_DIR) };
+        env_builder.max_dbs(MAX_DBS);
+        env_builder.map_size(options.initial_map_size);
+        unsafe { env_builder.flags(flags) };
 
         let env = unsafe { env_builder.open(path.join("evm.mdb")) }?;
 
-        Self::new_with_env(env)
+        Self::new_with_env_and_progress(env, options, print_migration_progress)
+    }
+
+    /// Opens an existing database without writing to it, e.g. to inspect it while a node is
+    /// running. Unlike [`Self::new`] nothing is created or migrated.
+    pub fn open_read_only(path: PathBuf) -> Result<Self, Error> {
+        let file = path.join("evm.mdb");
+        if !file.is_file() {
+            return Err(Error::NotFound(file));
+        }
+
+        let mut env_builder = EnvOpenOptions::new();
+        env_builder.max_dbs(MAX_DBS);
+        unsafe { env_builder.flags(EnvFlags::NO_SUB_DIR | EnvFlags::READ_ONLY) };
+
+        let env = unsafe { env_builder.open(file) }?;
+
+        let rtxn = env.read_txn()?;
+        let inner = InnerStorage {
+            accounts: open_table(&env, &rtxn, "accounts")?,
+            commits: open_table(&env, &rtxn, "commits")?,
+            contracts: open_table(&env, &rtxn, "contracts")?,
+            metadata: open_table(&env, &rtxn, "metadata")?,
+            storage: open_table(&env, &rtxn, "storage")?,
+            validator_sets: open_table(&env, &rtxn, "validator_sets")?,
+            voters: open_table(&env, &rtxn, "voters")?,
+        };
+
+        let schema_version = inner
+            .metadata
+            .get(&rtxn, migrations::SCHEMA_VERSION_KEY)?
+            .unwrap_or_default();
+
+        // keeps the database handles valid after the transaction
+        rtxn.commit()?;
+
+        if schema_version != migrations::SCHEMA_VERSION {
+            return Err(Error::MigrationRequired {
+                found: schema_version,
+                expected: migrations::SCHEMA_VERSION,
+            });
+        }
+
+        Ok(Self {
+            env,
+            inner: RefCell::new(inner),
+            schema_version,
+            map_size_growth: Default::default(),
+            max_map_size: None,
+            pruning_mode: Default::default(),
+            last_prune: Default::default(),
+            resizes: Default::default(),
+            genesis_info: None,
+        })
     }
 
     pub fn new_with_env(env: heed::Env) -> Result<Self, Error> {
+        Self::new_with_env_and_progress(env, Default::default(), print_migration_progress)
+    }
+
+    pub fn new_with_env_and_progress(
+        env: heed::Env,
+        options: PersistentDBOptions,
+        mut progress: impl FnMut(MigrationProgress),
+    ) -> Result<Self, Error> {
         let real_disk_size = env.real_disk_size()?;
         if real_disk_size >= env.info().map_size as u64 {
             // ensure initial map size is always larger than disk size
-            unsafe { env.resize(next_map_size(real_disk_size as usize))? };
+            unsafe {
+                env.resize(
+                    options
+                        .map_size_growth
+                        .next_map_size(real_disk_size as usize),
+                )?
+            };
         }
 
         let tx_env = env.clone();
@@ -138,6 +409,8 @@ impl PersistentDB {
                 &mut wtxn,
                 Some("contracts"),
             )?;
+        let metadata =
+            env.create_database::<heed::types::Str, HeedU64>(&mut wtxn, Some("metadata"))?;
 
         let storage = env
             .database_options()
@@ -146,20 +419,82 @@ impl PersistentDB {
             .flags(heed::DatabaseFlags::DUP_SORT)
             .create(&mut wtxn)?;
 
+        let validator_sets = env
+            .create_database::<HeedHeight, heed::types::SerdeBincode<ValidatorSet>>(
+                &mut wtxn,
+                Some("validator_sets"),
+            )?;
+        let voters = env.create_database::<AddressWrapper, heed::types::SerdeBincode<Address>>(
+            &mut wtxn,
+            Some("voters"),
+        )?;
+
         wtxn.commit()?;
 
+        let inner = InnerStorage {
+            accounts,
+            commits,
+            contracts,
+            metadata,
+            storage,
+            validator_sets,
+            voters,
+        };
+
+        let schema_version = migrations::run(&env, &inner, &mut progress)?;
+
         Ok(Self {
             env,
-            inner: RefCell::new(InnerStorage {
-                accounts,
-                commits,
-                contracts,
-                storage,
-            }),
+            inner: RefCell::new(inner),
+            schema_version,
+            map_size_growth: options.map_size_growth,
+            max_map_size: options.max_map_size,
+            pruning_mode: options.pruning_mode,
+            last_prune: Default::default(),
+            resizes: Default::default(),
             genesis_info: None,
         })
     }
 
+    /// Closes the environment and waits until it is released, so the same database can be
+    /// opened again in this process, e.g. with different options.
+    pub fn close(self) {
+        let closing = self.env.clone().prepare_for_closing();
+        drop(self);
+        closing.wait();
+    }
+
+    pub fn set_pruning_mode(&mut self, pruning_mode: PruningMode) {
+        self.pruning_mode = pruning_mode;
+    }
+
+    pub fn pruning_mode(&self) -> PruningMode {
+        self.pruning_mode
+    }
+
+    /// Returns the receipts pruned by the last commit and in total over the database lifetime.
+    pub fn pruning_stats(&self) -> Result<(PruneStats, PruneStats), Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let total = PruneStats {
+            heights: inner
+                .metadata
+                .get(&rtxn, pruning::PRUNED_HEIGHTS_KEY)?
+                .unwrap_or_default(),
+            receipts: inner
+                .metadata
+                .get(&rtxn, pruning::PRUNED_RECEIPTS_KEY)?
+                .unwrap_or_default(),
+        };
+
+        Ok((self.last_prune.get(), total))
+    }
+
+    pub fn schema_version(&self) -> u64 {
+        self.schema_version
+    }
+
     pub fn set_genesis_info(&mut self, genesis_info: GenesisInfo) {
         self.genesis_info.replace(genesis_info);
     }
@@ -169,19 +504,97 @@ impl PersistentDB {
 
         let current_map_size = info.map_size;
 
-        let next_map_size = next_map_size(current_map_size);
+        let mut next_map_size = self.map_size_growth.next_map_size(current_map_size);
+        if let Some(max_map_size) = self.max_map_size {
+            if current_map_size >= max_map_size {
+                return Err(Error::MaxMapSizeReached(max_map_size));
+            }
 
-        println!("resizing db {} -> {}", current_map_size, next_map_size);
+            next_map_size = next_map_size.min(max_map_size);
+        }
 
         unsafe { self.env.resize(next_map_size)? };
 
+        self.resizes.set(self.resizes.get() + 1);
+
         Ok(())
     }
+
+    pub fn last_committed_height(&self) -> Result<Option<u64>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        Ok(inner
+            .commits
+            .remap_data_type::<heed::types::DecodeIgnore>()
+            .last(&rtxn)?
+            .map(|(height, _)| height))
+    }
+
+    pub fn stats(&self) -> Result<DbStats, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let tables = [
+            ("accounts", inner.accounts.stat(&rtxn)?),
+            ("commits", inner.commits.stat(&rtxn)?),
+            ("contracts", inner.contracts.stat(&rtxn)?),
+            ("metadata", inner.metadata.stat(&rtxn)?),
+            ("storage", inner.storage.stat(&rtxn)?),
+            ("validator_sets", inner.validator_sets.stat(&rtxn)?),
+            ("voters", inner.voters.stat(&rtxn)?),
+        ];
+
+        let page_size = tables[0].1.page_size;
+        let tables = tables
+            .into_iter()
+            .map(|(name, stat)| TableStats {
+                name,
+                entries: stat.entries as u64,
+                depth: stat.depth,
+                branch_pages: stat.branch_pages as u64,
+                leaf_pages: stat.leaf_pages as u64,
+                overflow_pages: stat.overflow_pages as u64,
+            })
+            .collect();
+
+        drop(inner);
+        drop(rtxn);
+
+        let last_committed_height = self.last_committed_height()?;
+        let (last_prune, total_pruned) = self.pruning_stats()?;
+
+        Ok(DbStats {
+            tables,
+            page_size,
+            map_size: self.env.info().map_size as u64,
+            real_disk_size: self.env.real_disk_size()?,
+            last_committed_height,
+            resizes: self.resizes.get(),
+            last_prune,
+            total_pruned,
+        })
+    }
 }
 
+const MAX_DBS: u32 = 7;
 const MAP_SIZE_UNIT: usize = 1024 * 1024 * 1024; // 1 GB
-fn next_map_size(map_size: usize) -> usize {
-    map_size / MAP_SIZE_UNIT * MAP_SIZE_UNIT + MAP_SIZE_UNIT
+const MAP_SIZE_ALIGNMENT: usize = 64 * 1024; // covers all common OS page sizes
+
+fn open_table<KC: 'static, DC: 'static>(
+    env: &heed::Env,
+    rtxn: &heed::RoTxn,
+    name: &'static str,
+) -> Result<heed::Database<KC, DC>, Error> {
+    env.open_database(rtxn, Some(name))?
+        .ok_or(Error::MissingTable(name))
+}
+
+fn print_migration_progress(progress: MigrationProgress) {
+    println!(
+        "migrating evm db v{} -> v{} ({}): {}/{}",
+        progress.from, progress.to, progress.description, progress.processed, progress.total
+    );
 }
 
 impl Database for PersistentDB {
@@ -208,28 +621,104 @@ impl DatabaseRef for PersistentDB {
     type Error = Error;
 
     fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.read_view().basic(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.read_view().code_by_hash(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.read_view().storage(address, index)
+    }
+
+    // Block hashes are not known to the EVM, `BLOCKHASH` returns zero for every height.
+    fn block_hash_ref(&self, _number: u64) -> Result<B256, Self::Error> {
+        Ok(B256::ZERO)
+    }
+}
+
+/// Read-only handle to the committed state which, unlike `PersistentDB`, can be shared
+/// between threads. Every read opens its own read transaction.
+#[derive(Clone)]
+pub struct DbReader {
+    env: heed::Env,
+    inner: InnerStorage,
+}
+
+impl PersistentDB {
+    pub fn reader(&self) -> DbReader {
+        DbReader {
+            env: self.env.clone(),
+            inner: *self.inner.borrow(),
+        }
+    }
+
+    fn read_view(&self) -> ReadView<'_> {
+        ReadView {
+            env: &self.env,
+            inner: *self.inner.borrow(),
+        }
+    }
+}
+
+impl DatabaseRef for DbReader {
+    type Error = Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.read_view().basic(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.read_view().code_by_hash(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.read_view().storage(address, index)
+    }
+
+    // Block hashes are not known to the EVM, `BLOCKHASH` returns zero for every height.
+    fn block_hash_ref(&self, _number: u64) -> Result<B256, Self::Error> {
+        Ok(B256::ZERO)
+    }
+}
+
+impl DbReader {
+    fn read_view(&self) -> ReadView<'_> {
+        ReadView {
+            env: &self.env,
+            inner: self.inner,
+        }
+    }
+}
+
+// Reads shared by `PersistentDB` and `DbReader`.
+struct ReadView<'a> {
+    env: &'a heed::Env,
+    inner: InnerStorage,
+}
+
+impl ReadView<'_> {
+    fn basic(&self, address: Address) -> Result<Option<AccountInfo>, Error> {
         let txn = self.env.read_txn()?;
-        let inner = self.inner.borrow();
 
-        let basic = match inner.accounts.get(&txn, &AddressWrapper(address))? {
-            Some(account) => account,
-            None => match &self.genesis_info {
-                Some(genesis) if genesis.account == address => revm::primitives::AccountInfo {
-                    balance: genesis.initial_supply,
-                    ..Default::default()
-                },
-                _ => AccountInfo::default(),
-            },
-        };
+        let basic = self
+            .inner
+            .accounts
+            .get(&txn, &AddressWrapper(address))?
+            .unwrap_or_default();
 
         Ok(basic.into())
     }
 
-    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+    fn code_by_hash(&self, code_hash: B256) -> Result<Bytecode, Error> {
         let txn = self.env.read_txn()?;
-        let inner = self.inner.borrow();
 
-        let contract = match inner.contracts.get(&txn, &ContractWrapper(code_hash))? {
+        let contract = match self
+            .inner
+            .contracts
+            .get(&txn, &ContractWrapper(code_hash))?
+        {
             Some(contract) => contract,
             None => Default::default(),
         };
@@ -237,11 +726,11 @@ impl DatabaseRef for PersistentDB {
         Ok(contract)
     }
 
-    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+    fn storage(&self, address: Address, index: U256) -> Result<U256, Error> {
         let txn = self.env.read_txn()?;
-        let inner = self.inner.borrow_mut();
 
-        let dups = inner
+        let dups = self
+            .inner
             .storage
             .get_duplicates(&txn, &AddressWrapper(address))?;
 
@@ -259,21 +748,11 @@ impl DatabaseRef for PersistentDB {
 
         Ok(U256::ZERO)
     }
-
-    fn block_hash_ref(&self, _number: u64) -> Result<B256, Self::Error> {
-        todo!()
-    }
 }
 
 impl PersistentDB {
     pub fn commit(&self, state_commit: &mut StateCommit) -> Result<(), Error> {
-        let StateCommit {
-            key,
-            ref mut change_set,
-            ref results,
-        } = state_commit;
-
-        match self.commit_to_db(*key, change_set, results) {
+        match self.commit_to_db(state_commit) {
             Ok(_) => return Ok(()),
             Err(err) => match &err {
                 Error::Heed(heed_err) => match heed_err {
@@ -288,24 +767,37 @@ impl PersistentDB {
         }
     }
 
-    fn commit_to_db(
-        &self,
-        key: CommitKey,
-        change_set: &mut state_changes::StateChangeset,
-        results: &BTreeMap<B256, ExecutionResult>,
-    ) -> Result<(), Error> {
+    fn commit_to_db(&self, state_commit: &mut StateCommit) -> Result<(), Error> {
+        let StateCommit {
+            key,
+            ref mut change_set,
+            ref results,
+            randomness,
+            gas_used,
+            ref positions,
+            ref validator_set,
+            ref system_results,
+            ref penalties,
+        } = *state_commit;
+
         assert!(!self.is_height_committed(key.0));
 
         let mut rwtxn = self.env.write_txn()?;
         let inner = self.inner.borrow_mut();
 
-        let mut apply_changes = |rwtxn: &mut heed::RwTxn| -> Result<(), Error> {
+        let mut apply_changes = |rwtxn: &mut heed::RwTxn| -> Result<PruneStats, Error> {
             let state_changes::StateChangeset {
                 ref mut accounts,
                 ref mut storage,
                 ref mut contracts,
             } = change_set;
 
+            // votes can only be indexed completely if indexing starts with the first commit, which
+            // need not be height 0, e.g. the consensus contract is deployed before the genesis
+            let index_complete = self.genesis_info.is_some()
+                && inner.commits.is_empty(rwtxn)?
+                && inner.accounts.is_empty(rwtxn)?;
+
             accounts.par_sort_by_key(|a| a.0);
             contracts.par_sort_by_key(|a| a.0);
             storage.par_sort_by_key(|a| a.address);
@@ -360,9 +852,23 @@ impl PersistentDB {
             }
 
             // Finalize commit
+            let pruned = self.pruning_mode == PruningMode::HashesOnly;
+
             let mut tx_receipts = HashMap::new();
-            for (k, result) in results {
-                tx_receipts.insert(k.clone(), map_execution_result(result.clone()));
+            if !pruned {
+                for (k, result) in results {
+                    let position = positions.get(k).copied().unwrap_or_default();
+                    tx_receipts.insert(
+                        k.clone(),
+                        map_execution_result(result.clone()).with_position(position),
+                    );
+                }
+
+                tx_receipts.extend(map_system_results(
+                    system_results,
+                    positions.len() as u64,
+                    gas_used,
+                ));
             }
 
             inner.commits.put(
@@ -372,20 +878,43 @@ impl PersistentDB {
                     accounts_hash: state_hash::calculate_accounts_hash(&change_set)?,
                     contracts_hash: state_hash::calculate_contracts_hash(&change_set)?,
                     storage_hash: state_hash::calculate_storage_hash(&change_set)?,
+                    randomness,
+                    gas_used,
                     tx_receipts,
+                    pruned,
+                    penalties: penalties.clone(),
                 },
             )?;
 
-            Ok(())
+            if let Some(validator_set) = validator_set {
+                inner.validator_sets.put(rwtxn, &key.0, validator_set)?;
+            }
+
+            if let Some(genesis_info) = &self.genesis_info {
+                let changes =
+                    voters::vote_changes(genesis_info.validator_contract, results, positions);
+                voters::index(&inner, rwtxn, changes)?;
+
+                if index_complete {
+                    inner.metadata.put(rwtxn, voters::VOTER_INDEX_KEY, &1)?;
+                }
+            }
+
+            pruning::prune(&inner, rwtxn, self.pruning_mode, key.0)
         };
 
-        if let Err(err) = apply_changes(&mut rwtxn) {
-            rwtxn.abort();
-            return Err(err.into());
-        }
+        let prune_stats = match apply_changes(&mut rwtxn) {
+            Ok(prune_stats) => prune_stats,
+            Err(err) => {
+                rwtxn.abort();
+                return Err(err.into());
+            }
+        };
 
         rwtxn.commit()?;
 
+        self.last_prune.set(prune_stats);
+
         Ok(())
     }
 
@@ -407,6 +936,7 @@ impl PersistentDB {
         let inner = self.inner.borrow();
 
         match inner.commits.get(&rtxn, &height)? {
+            Some(receipts) if receipts.pruned => Err(Error::ReceiptsPruned(height)),
             Some(receipts) => Ok((true, receipts.tx_receipts.get(&tx_hash).cloned())),
             None => Ok((false, None)),
         }
@@ -426,6 +956,79 @@ impl PersistentDB {
             None => Ok(None),
         }
     }
+
+    /// Randomness the commit at `height` was executed with.
+    pub fn get_committed_randomness(&self, height: u64) -> Result<Option<B256>, Error> {
+        let env = self.env.clone();
+        let rtxn = env.read_txn().expect("read");
+        let inner = self.inner.borrow();
+
+        Ok(inner
+            .commits
+            .get(&rtxn, &height)?
+            .map(|receipts| receipts.randomness))
+    }
+
+    /// Penalties applied in the commit at `height`, in the order they were applied.
+    pub fn get_committed_penalties(
+        &self,
+        height: u64,
+    ) -> Result<Option<Vec<AppliedPenalty>>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        Ok(inner
+            .commits
+            .get(&rtxn, &height)?
+            .map(|receipts| receipts.penalties))
+    }
+
+    /// Whether every vote is in the voter index, see [`voters::changed_voters`].
+    pub fn has_voter_index(&self) -> Result<bool, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        Ok(inner
+            .metadata
+            .get(&rtxn, voters::VOTER_INDEX_KEY)?
+            .is_some())
+    }
+
+    /// Validator `voter` votes for according to the voter index.
+    pub fn get_voted_validator(&self, voter: Address) -> Result<Option<Address>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        Ok(inner.voters.get(&rtxn, &AddressWrapper(voter))?)
+    }
+
+    /// Validator set active at `height`, i.e. the one calculated last in a commit below it.
+    pub fn get_validator_set_at(&self, height: u64) -> Result<Option<ValidatorSet>, Error> {
+        let env = self.env.clone();
+        let rtxn = env.read_txn().expect("read");
+        let inner = self.inner.borrow();
+
+        let validator_set = inner
+            .validator_sets
+            .rev_range(&rtxn, &(..height))?
+            .next()
+            .transpose()?
+            .map(|(_, validator_set)| validator_set);
+
+        Ok(validator_set)
+    }
+
+    /// Gas used by all transactions of the commit at `height`.
+    pub fn get_committed_gas_used(&self, height: u64) -> Result<Option<u64>, Error> {
+        let env = self.env.clone();
+        let rtxn = env.read_txn().expect("read");
+        let inner = self.inner.borrow();
+
+        Ok(inner
+            .commits
+            .get(&rtxn, &height)?
+            .map(|receipts| receipts.gas_used))
+    }
 }
 
 impl PendingCommit {
@@ -435,10 +1038,109 @@ impl PendingCommit {
             cache: Default::default(),
             results: Default::default(),
             transitions: Default::default(),
+            randomness: Default::default(),
+            gas_used: 0,
+            positions: Default::default(),
+            fees: U256::ZERO,
+            fee_distribution: None,
+            distributed_fees: None,
+            rewards: Vec::new(),
+            penalties: Vec::new(),
+            validator_set: None,
+            system_results: Vec::new(),
+        }
+    }
+
+    /// Fails if a transaction with `gas_limit` does not fit into the gas left in a block with
+    /// `block_gas_limit` after the transactions executed so far.
+    pub fn check_block_gas(&self, block_gas_limit: u64, gas_limit: u64) -> Result<(), Error> {
+        let remaining = block_gas_limit.saturating_sub(self.gas_used);
+        if gas_limit > remaining {
+            return Err(Error::BlockGasLimitExceeded {
+                gas_limit,
+                remaining,
+            });
+        }
+
+        Ok(())
+    }
+
+    /// Records the result of the transaction `tx_hash` after the ones executed so far.
+    pub fn add_result(&mut self, tx_hash: B256, result: ExecutionResult) -> TxPosition {
+        self.gas_used += result.gas_used();
+
+        let position = TxPosition {
+            index: self.positions.len() as u64,
+            cumulative_gas_used: self.gas_used,
+        };
+
+        self.results.insert(tx_hash, result);
+        self.positions.insert(tx_hash, position);
+
+        position
+    }
+
+    pub fn stats(&self) -> PendingCommitStats {
+        PendingCommitStats {
+            accounts: self.cache.accounts.len() as u64,
+            storage_slots: self
+                .cache
+                .accounts
+                .values()
+                .filter_map(|account| account.account.as_ref())
+                .map(|account| account.storage.len() as u64)
+                .sum(),
+            transactions: self.results.len() as u64,
         }
     }
 }
 
+/// Reads through the cache of a pending commit before falling back to `db`, returning what
+/// the state will look like once the pending commit is committed. Without a cache it reads
+/// `db` only.
+pub struct PendingStateRef<'a, DB> {
+    pub db: DB,
+    pub cache: Option<&'a CacheState>,
+}
+
+impl<DB: DatabaseRef> DatabaseRef for PendingStateRef<'_, DB> {
+    type Error = DB::Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        match self.cache.and_then(|cache| cache.accounts.get(&address)) {
+            Some(account) => Ok(account.account_info()),
+            None => self.db.basic_ref(address),
+        }
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        match self.cache.and_then(|cache| cache.contracts.get(&code_hash)) {
+            Some(code) => Ok(code.clone()),
+            None => self.db.code_by_hash_ref(code_hash),
+        }
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        let Some(cached) = self.cache.and_then(|cache| cache.accounts.get(&address)) else {
+            return self.db.storage_ref(address, index);
+        };
+
+        // same lookup as `State::storage`
+        match &cached.account {
+            Some(account) => match account.storage.get(&index) {
+                Some(value) => Ok(*value),
+                None if cached.status.is_storage_known() => Ok(U256::ZERO),
+                None => self.db.storage_ref(address, index),
+            },
+            None => Ok(U256::ZERO),
+        }
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash_ref(number)
+    }
+}
+
 #[test]
 fn test_open_db() {
     let tmp = tempfile::Builder::new()
@@ -509,6 +1211,7 @@ fn test_commit_changes() {
             cache: CacheState::default(),
             results: Default::default(),
             transitions: TransitionState { transitions: state },
+            ..Default::default()
         },
     )
     .expect("ok");
@@ -589,6 +1292,7 @@ fn test_storage() {
             cache: CacheState::default(),
             results: Default::default(),
             transitions: TransitionState { transitions: state },
+            ..Default::default()
         },
     )
     .expect("ok");
@@ -649,6 +1353,7 @@ fn test_storage_overwrite() {
             cache: CacheState::default(),
             results: Default::default(),
             transitions: TransitionState { transitions: state },
+            ..Default::default()
         },
     )
     .expect("ok");
@@ -686,6 +1391,7 @@ fn test_storage_overwrite() {
             cache: CacheState::default(),
             results: Default::default(),
             transitions: TransitionState { transitions: state },
+            ..Default::default()
         },
     )
     .expect("ok");
@@ -705,59 +1411,69 @@ fn test_storage_overwrite() {
 fn test_next_map_size() {
     let input = vec![0, 1, 2, 3, 4];
     for i in input {
-        let next = next_map_size(i * MAP_SIZE_UNIT);
+        let next = MapSizeGrowth::Step(MAP_SIZE_UNIT).next_map_size(i * MAP_SIZE_UNIT);
         assert_eq!(next, (i + 1) * MAP_SIZE_UNIT);
     }
-}
 
-#[test]
-fn test_resize_on_commit() {
-    let create_large_commit = |height: u64, n: usize| {
-        let mut buf = vec![0; 32];
-        buf[0..8].copy_from_slice(&height.to_le_bytes());
-        let address = Address::from_word(ethers_core::utils::keccak256(buf).into());
+    let growth = MapSizeGrowth::Factor(1.5);
+    assert_eq!(growth.next_map_size(2 * MAP_SIZE_UNIT), 3 * MAP_SIZE_UNIT);
+    assert_eq!(growth.next_map_size(0), MAP_SIZE_ALIGNMENT);
+    assert_eq!(
+        growth.next_map_size(MAP_SIZE_ALIGNMENT + 1),
+        2 * MAP_SIZE_ALIGNMENT
+    );
+}
 
-        let mut state = HashMap::new();
+#[cfg(test)]
+fn create_large_commit(height: u64, n: usize) -> PendingCommit {
+    let mut buf = vec![0; 32];
+    buf[0..8].copy_from_slice(&height.to_le_bytes());
+    let address = Address::from_word(ethers_core::utils::keccak256(buf).into());
 
-        let mut account = Account::new_not_existing();
-        account.status = AccountStatus::Touched;
+    let mut state = HashMap::new();
 
-        let mut storage = HashMap::new();
+    let mut account = Account::new_not_existing();
+    account.status = AccountStatus::Touched;
 
-        for i in 0..n {
-            storage.insert(
-                U256::from(i + 1),
-                revm::db::states::StorageSlot::new_changed(U256::ZERO, U256::from(1)),
-            );
-        }
+    let mut storage = HashMap::new();
 
-        state.insert(
-            address,
-            revm::db::TransitionAccount {
-                status: revm::db::AccountStatus::InMemoryChange,
-                info: Some(account.info.clone()),
-                previous_status: revm::db::AccountStatus::Loaded,
-                previous_info: None,
-                storage,
-                storage_was_destroyed: false,
-            },
+    for i in 0..n {
+        storage.insert(
+            U256::from(i + 1),
+            revm::db::states::StorageSlot::new_changed(U256::ZERO, U256::from(1)),
         );
+    }
 
-        PendingCommit {
-            key: CommitKey(height, 0),
-            cache: CacheState::default(),
-            results: Default::default(),
-            transitions: TransitionState { transitions: state },
-        }
-    };
+    state.insert(
+        address,
+        revm::db::TransitionAccount {
+            status: revm::db::AccountStatus::InMemoryChange,
+            info: Some(account.info.clone()),
+            previous_status: revm::db::AccountStatus::Loaded,
+            previous_info: None,
+            storage,
+            storage_was_destroyed: false,
+        },
+    );
+
+    PendingCommit {
+        key: CommitKey(height, 0),
+        cache: CacheState::default(),
+        results: Default::default(),
+        transitions: TransitionState { transitions: state },
+        ..Default::default()
+    }
+}
 
+#[test]
+fn test_resize_on_commit() {
     let path = tempfile::Builder::new()
         .prefix("evm.mdb")
         .tempdir()
         .unwrap();
 
     let mut env_builder = EnvOpenOptions::new();
-    env_builder.max_dbs(4);
+    env_builder.max_dbs(MAX_DBS);
     env_builder.map_size(4096 * 10); // start with very small (few kB)
 
     unsafe { env_builder.flags(EnvFlags::NO_SUB_DIR) };
@@ -786,3 +1502,358 @@ fn test_resize_on_commit() {
     let db = PersistentDB::new_with_env(env).expect("open");
     assert_eq!(db.env.info().map_size, MAP_SIZE_UNIT);
 }
+
+#[test]
+fn test_schema_version_fresh_db() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    assert_eq!(db.schema_version(), migrations::SCHEMA_VERSION);
+
+    // reopening keeps the stored version
+    drop(db);
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    assert_eq!(db.schema_version(), migrations::SCHEMA_VERSION);
+}
+
+#[test]
+fn test_schema_version_refuses_newer_db() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    {
+        let mut wtxn = db.env.write_txn().unwrap();
+        let inner = db.inner.borrow();
+        inner
+            .metadata
+            .put(
+                &mut wtxn,
+                migrations::SCHEMA_VERSION_KEY,
+                &(migrations::SCHEMA_VERSION + 1),
+            )
+            .unwrap();
+        wtxn.commit().unwrap();
+    }
+
+    let env = db.env.clone();
+    drop(db);
+
+    assert!(matches!(
+        PersistentDB::new_with_env(env),
+        Err(Error::UnsupportedSchemaVersion { found, supported })
+            if found == migrations::SCHEMA_VERSION + 1 && supported == migrations::SCHEMA_VERSION
+    ));
+}
+
+#[test]
+fn test_committed_randomness() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    db.commit(&mut StateCommit {
+        key: CommitKey(3, 0),
+        randomness: B256::repeat_byte(7),
+        ..Default::default()
+    })
+    .expect("commit");
+
+    assert_eq!(
+        db.get_committed_randomness(3).expect("randomness"),
+        Some(B256::repeat_byte(7))
+    );
+    assert_eq!(db.get_committed_randomness(4).expect("randomness"), None);
+}
+
+#[test]
+fn test_validator_set_at() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    let validator_set = |height, round, address: Address| ValidatorSet {
+        height,
+        round,
+        validators: vec![ValidatorSetEntry {
+            address,
+            bls_public_key: Bytes::from_static(&[1; 48]),
+            vote_balance: U256::from(100),
+            voters_count: U256::from(2),
+        }],
+    };
+
+    let first = validator_set(0, 0, Address::repeat_byte(1));
+    let second = validator_set(4, 2, Address::repeat_byte(2));
+
+    for (height, validator_set) in [(0, Some(&first)), (2, None), (4, Some(&second))] {
+        db.commit(&mut StateCommit {
+            key: CommitKey(height, validator_set.map_or(0, |set| set.round)),
+            validator_set: validator_set.cloned(),
+            ..Default::default()
+        })
+        .expect("commit");
+    }
+
+    assert_eq!(db.get_validator_set_at(0).expect("validator set"), None);
+    assert_eq!(
+        db.get_validator_set_at(1).expect("validator set"),
+        Some(first.clone())
+    );
+    assert_eq!(
+        db.get_validator_set_at(4).expect("validator set"),
+        Some(first)
+    );
+    assert_eq!(
+        db.get_validator_set_at(5).expect("validator set"),
+        Some(second.clone())
+    );
+    assert_eq!(
+        db.get_validator_set_at(100).expect("validator set"),
+        Some(second)
+    );
+}
+
+#[test]
+fn test_stats() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    let stats = db.stats().expect("stats");
+    assert_eq!(stats.last_committed_height, None);
+    assert_eq!(stats.resizes, 0);
+    assert_eq!(stats.map_size, MAP_SIZE_UNIT as u64);
+    assert_eq!(
+        stats.tables.iter().map(|t| t.name).collect::<Vec<_>>(),
+        vec![
+            "accounts",
+            "commits",
+            "contracts",
+            "metadata",
+            "storage",
+            "validator_sets",
+            "voters"
+        ]
+    );
+
+    for height in [0, 1, 5] {
+        db.commit(&mut StateCommit {
+            key: CommitKey(height, 0),
+            ..Default::default()
+        })
+        .expect("commit");
+    }
+    db.resize().expect("resize");
+
+    let stats = db.stats().expect("stats");
+    assert_eq!(stats.last_committed_height, Some(5));
+    assert_eq!(stats.resizes, 1);
+    assert_eq!(stats.map_size, 2 * MAP_SIZE_UNIT as u64);
+    assert_eq!(stats.tables[1].entries, 3);
+}
+
+#[test]
+fn test_resize_until_max_map_size() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new_with_options(
+        path.path().to_path_buf(),
+        PersistentDBOptions {
+            initial_map_size: 4 * MAP_SIZE_ALIGNMENT,
+            map_size_growth: MapSizeGrowth::Step(MAP_SIZE_ALIGNMENT),
+            max_map_size: Some(16 * MAP_SIZE_ALIGNMENT),
+            sync_mode: SyncMode::NoSync,
+            ..Default::default()
+        },
+    )
+    .expect("open");
+
+    // fits after growing in several small steps
+    crate::state_commit::commit_to_db(&mut db, create_large_commit(0, 4 * 1024)).expect("ok");
+
+    let stats = db.stats().expect("stats");
+    assert!(stats.resizes > 1);
+    assert!(stats.map_size <= 16 * MAP_SIZE_ALIGNMENT as u64);
+
+    // does not fit into the capped map size
+    assert!(matches!(
+        crate::state_commit::commit_to_db(&mut db, create_large_commit(1, 64 * 1024)),
+        Err(Error::MaxMapSizeReached(max_map_size)) if max_map_size == 16 * MAP_SIZE_ALIGNMENT
+    ));
+    assert_eq!(db.env.info().map_size, 16 * MAP_SIZE_ALIGNMENT);
+    assert!(!db.is_height_committed(1));
+}
+
+#[test]
+fn test_pending_state_ref() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    let address = address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    let destroyed = address!("0000000000000000000000000000000000000001");
+
+    let mut transitions = HashMap::new();
+    for account in [address, destroyed] {
+        let mut storage = HashMap::new();
+        for i in 1..3 {
+            storage.insert(
+                U256::from(i),
+                revm::db::states::StorageSlot::new_changed(U256::ZERO, U256::from(i)),
+            );
+        }
+
+        transitions.insert(
+            account,
+            revm::db::TransitionAccount {
+                status: revm::db::AccountStatus::InMemoryChange,
+                info: Some(AccountInfo {
+                    balance: U256::from(100),
+                    nonce: 1,
+                    ..Default::default()
+                }),
+                previous_status: revm::db::AccountStatus::Loaded,
+                previous_info: None,
+                storage,
+                storage_was_destroyed: false,
+            },
+        );
+    }
+
+    crate::state_commit::commit_to_db(
+        &mut db,
+        PendingCommit {
+            key: CommitKey(0, 0),
+            transitions: TransitionState { transitions },
+            ..Default::default()
+        },
+    )
+    .expect("ok");
+
+    let mut pending = PendingCommit::new(CommitKey(1, 0));
+    pending.cache.insert_account_with_storage(
+        address,
+        AccountInfo {
+            balance: U256::from(50),
+            nonce: 2,
+            ..Default::default()
+        },
+        [(U256::from(1), U256::from(10))].into_iter().collect(),
+    );
+    pending.cache.insert_not_existing(destroyed);
+
+    let committed = PendingStateRef {
+        db: &db,
+        cache: None,
+    };
+    let state = PendingStateRef {
+        db: &db,
+        cache: Some(&pending.cache),
+    };
+
+    assert_eq!(
+        committed
+            .basic_ref(address)
+            .expect("basic")
+            .map(|a| a.nonce),
+        Some(1)
+    );
+    assert_eq!(
+        state.basic_ref(address).expect("basic").map(|a| a.nonce),
+        Some(2)
+    );
+    assert_eq!(state.basic_ref(destroyed).expect("basic"), None);
+
+    // slots missing in the cache are read from the database unless the account was destroyed
+    assert_eq!(
+        committed
+            .storage_ref(address, U256::from(1))
+            .expect("storage"),
+        U256::from(1)
+    );
+    assert_eq!(
+        state.storage_ref(address, U256::from(1)).expect("storage"),
+        U256::from(10)
+    );
+    assert_eq!(
+        state.storage_ref(address, U256::from(2)).expect("storage"),
+        U256::from(2)
+    );
+    assert_eq!(
+        state
+            .storage_ref(destroyed, U256::from(2))
+            .expect("storage"),
+        U256::ZERO
+    );
+}
+
+#[test]
+fn test_open_read_only() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    // nothing is created for a missing database
+    assert!(matches!(
+        PersistentDB::open_read_only(path.path().join("missing")),
+        Err(Error::NotFound(_))
+    ));
+    assert!(!path.path().join("missing").exists());
+
+    let address = address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    {
+        let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+        let mut pending = PendingCommit::new(CommitKey(0, 0));
+        crate::state_commit::apply_rewards(&mut db, &mut pending, [(address, 100)].into())
+            .expect("rewards");
+        crate::state_commit::commit_to_db(&mut db, pending).expect("commit");
+        db.close();
+    }
+
+    let modified = std::fs::metadata(path.path().join("evm.mdb"))
+        .and_then(|metadata| metadata.modified())
+        .unwrap();
+
+    let db = PersistentDB::open_read_only(path.path().to_path_buf()).expect("read-only");
+    assert_eq!(db.schema_version(), migrations::SCHEMA_VERSION);
+    assert_eq!(
+        db.basic_ref(address).expect("basic").map(|a| a.balance),
+        Some(U256::from(100))
+    );
+    assert!(db.is_height_committed(0));
+    assert!(db.verify().expect("verify").errors.is_empty());
+
+    // writes are refused
+    assert!(db.import_state(Default::default()).is_err());
+    drop(db);
+
+    assert_eq!(
+        std::fs::metadata(path.path().join("evm.mdb"))
+            .and_then(|metadata| metadata.modified())
+            .unwrap(),
+        modified
+    );
+}
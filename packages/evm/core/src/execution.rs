@@ -0,0 +1,931 @@
+use std::{cell::RefCell, collections::HashSet, sync::Arc};
+
+use rayon::prelude::*;
+use revm::{
+    db::WrapDatabaseRef,
+    handler::register::EvmHandler,
+    primitives::{
+        Account, AccountInfo, Address, BlockEnv, Bytecode, EVMError, ExecutionResult,
+        ResultAndState, SpecId, TxEnv, B256, U256,
+    },
+    CacheState, Database, DatabaseCommit, DatabaseRef, Evm, State, TransitionState,
+};
+
+use crate::{
+    db::{DbReader, Error, PendingCommit, PendingStateRef, PersistentDB},
+    precompiles::{consensus::ConsensusData, PrecompileRegistry},
+};
+
+/// A transaction executed as part of a pending commit.
+#[derive(Clone, Debug)]
+pub struct Transaction {
+    /// Results are only recorded in the pending commit if a hash is given.
+    pub tx_hash: Option<B256>,
+    pub env: TxEnv,
+}
+
+pub type TxOutcome = Result<ExecutionResult, EVMError<Error>>;
+
+#[derive(Debug)]
+pub struct ExecutionOutput {
+    /// One outcome per transaction, in the order they were given.
+    pub outcomes: Vec<TxOutcome>,
+    /// Number of transactions whose speculative result was discarded because of a conflict.
+    pub reexecuted: usize,
+}
+
+type CommitState<'a> = State<WrapDatabaseRef<&'a PersistentDB>>;
+
+/// Executes the transactions one after another on top of the pending commit.
+///
+/// Failed transactions leave the pending commit untouched, the remaining transactions are
+/// still executed. Transactions with a hash count towards the gas limit of `block_env` and are
+/// rejected if their gas limit exceeds the gas left in the block.
+///
+/// `consensus_data` is answered by the consensus data precompile, see
+/// [`PrecompileRegistry::handler_register_with_consensus_data`].
+pub fn execute_sequential(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    consensus_data: Option<&ConsensusData>,
+    transactions: Vec<Transaction>,
+) -> ExecutionOutput {
+    let mut state = build_state(db, pending);
+
+    let outcomes = transactions
+        .into_iter()
+        .map(|tx| {
+            check_block_gas(pending, block_env, &tx)?;
+            let result = transact(
+                &mut state,
+                pending,
+                block_env,
+                spec_id,
+                precompiles,
+                consensus_data,
+                tx.env,
+            )?;
+            Ok(apply(&mut state, pending, tx.tx_hash, result))
+        })
+        .collect();
+
+    pending.cache = std::mem::take(&mut state.cache);
+
+    ExecutionOutput {
+        outcomes,
+        reexecuted: 0,
+    }
+}
+
+/// Executes the transactions optimistically in parallel and commits them in order, producing
+/// the same results, transitions and cache as `execute_sequential`.
+///
+/// Every transaction first runs speculatively against the state of the pending commit as it
+/// was before the batch, recording which accounts and storage slots it read. The speculative
+/// results are then applied in order; a transaction that read anything written by an earlier
+/// transaction of the batch is executed again on top of the accumulated state instead.
+///
+/// The transaction fee is paid while applying, see [`credit_fee`], so that fee payments alone
+/// do not make every transaction conflict with its predecessors.
+pub fn execute_parallel(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    consensus_data: Option<&ConsensusData>,
+    transactions: Vec<Transaction>,
+) -> ExecutionOutput {
+    let reader = db.reader();
+    let speculative: Vec<Speculative> = transactions
+        .par_iter()
+        .map(|tx| {
+            speculate(
+                &reader,
+                &pending.cache,
+                block_env,
+                spec_id,
+                precompiles,
+                consensus_data,
+                tx.env.clone(),
+            )
+        })
+        .collect();
+
+    let mut state = build_state(db, pending);
+    let mut writes = WriteSet::default();
+    let mut reexecuted = 0;
+
+    let outcomes = transactions
+        .into_iter()
+        .zip(speculative)
+        .map(|(tx, speculative)| {
+            check_block_gas(pending, block_env, &tx)?;
+
+            let result = if writes.conflicts_with(&speculative.reads) {
+                reexecuted += 1;
+                transact(
+                    &mut state,
+                    pending,
+                    block_env,
+                    spec_id,
+                    precompiles,
+                    consensus_data,
+                    tx.env,
+                )?
+            } else {
+                // failed transactions load accounts into the cache as well
+                replay_reads(&mut state, &speculative.reads).map_err(EVMError::Database)?;
+                let mut result = speculative.result?;
+                credit_fee(
+                    &mut state,
+                    pending,
+                    &mut result,
+                    block_env.coinbase,
+                    speculative.fee,
+                )
+                .map_err(EVMError::Database)?;
+                result
+            };
+
+            writes.record(&result);
+
+            Ok(apply(&mut state, pending, tx.tx_hash, result))
+        })
+        .collect();
+
+    pending.cache = std::mem::take(&mut state.cache);
+
+    ExecutionOutput {
+        outcomes,
+        reexecuted,
+    }
+}
+
+/// Executes a system call on top of the pending commit, see [`system_call_handler`]. Its result
+/// is not recorded as a transaction of the pending commit.
+pub fn execute_system_call(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    tx_env: TxEnv,
+) -> TxOutcome {
+    let mut state = build_state(db, pending);
+
+    let result = Evm::builder()
+        .with_db(&mut state)
+        .with_spec_id(spec_id)
+        .with_block_env(block_env.clone())
+        .with_tx_env(tx_env)
+        .append_handler_register(system_call_handler)
+        .append_handler_register_box(precompiles.handler_register(block_env.number.saturating_to()))
+        .build()
+        .transact();
+    let outcome = result.map(|result| apply(&mut state, pending, None, result));
+
+    pending.cache = std::mem::take(&mut state.cache);
+
+    outcome
+}
+
+/// Executes a call against the committed state without recording or changing anything, e.g.
+/// for view calls outside of a commit.
+pub fn execute_view(
+    db: &PersistentDB,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    tx_env: TxEnv,
+) -> TxOutcome {
+    Evm::builder()
+        .with_ref_db(db)
+        .with_spec_id(spec_id)
+        .with_block_env(block_env.clone())
+        .with_tx_env(tx_env)
+        .append_handler_register_box(precompiles.handler_register(block_env.number.saturating_to()))
+        .build()
+        .transact()
+        .map(|result| result.result)
+}
+
+fn build_state<'a>(db: &'a PersistentDB, pending: &mut PendingCommit) -> CommitState<'a> {
+    State::builder()
+        .with_bundle_update()
+        .with_cached_prestate(std::mem::take(&mut pending.cache))
+        .with_database(WrapDatabaseRef(db))
+        .build()
+}
+
+fn check_block_gas(
+    pending: &PendingCommit,
+    block_env: &BlockEnv,
+    tx: &Transaction,
+) -> Result<(), EVMError<Error>> {
+    if tx.tx_hash.is_none() {
+        return Ok(());
+    }
+
+    pending
+        .check_block_gas(block_env.gas_limit.saturating_to(), tx.env.gas_limit)
+        .map_err(EVMError::Database)
+}
+
+fn transact(
+    state: &mut CommitState,
+    pending: &mut PendingCommit,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    consensus_data: Option<&ConsensusData>,
+    tx_env: TxEnv,
+) -> Result<ResultAndState, EVMError<Error>> {
+    let mut evm = Evm::builder()
+        .with_db(&mut *state)
+        .with_external_context(U256::ZERO)
+        .with_spec_id(spec_id)
+        .with_block_env(block_env.clone())
+        .with_tx_env(tx_env)
+        .append_handler_register(defer_beneficiary_reward)
+        .append_handler_register_box(precompiles.handler_register_with_consensus_data(
+            block_env.number.saturating_to(),
+            consensus_data.cloned(),
+        ))
+        .build();
+
+    let mut result = evm.transact()?;
+    let fee = evm.context.external;
+    drop(evm);
+
+    credit_fee(state, pending, &mut result, block_env.coinbase, fee).map_err(EVMError::Database)?;
+
+    Ok(result)
+}
+
+// Commits the state changes of a transaction and moves its transitions into the pending commit,
+// the same way a state built per transaction would.
+fn apply(
+    state: &mut CommitState,
+    pending: &mut PendingCommit,
+    tx_hash: Option<B256>,
+    ResultAndState {
+        result,
+        state: changes,
+    }: ResultAndState,
+) -> ExecutionResult {
+    state.commit(changes);
+
+    if let Some(tx_hash) = tx_hash {
+        pending.add_result(tx_hash, result.clone());
+    }
+
+    let transitions = state.transition_state.replace(TransitionState::default());
+    pending.transitions.add_transitions(
+        transitions
+            .unwrap_or_default()
+            .transitions
+            .into_iter()
+            .collect(),
+    );
+
+    result
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Location {
+    Account(Address),
+    Storage(Address, U256),
+    Code(B256),
+}
+
+struct Speculative {
+    result: Result<ResultAndState, EVMError<Error>>,
+    reads: HashSet<Location>,
+    fee: U256,
+}
+
+fn speculate(
+    reader: &DbReader,
+    cache: &CacheState,
+    block_env: &BlockEnv,
+    spec_id: SpecId,
+    precompiles: &PrecompileRegistry,
+    consensus_data: Option<&ConsensusData>,
+    tx_env: TxEnv,
+) -> Speculative {
+    let view = SpeculativeView {
+        state: PendingStateRef {
+            db: reader,
+            cache: Some(cache),
+        },
+        reads: Default::default(),
+    };
+
+    let mut evm = Evm::builder()
+        .with_ref_db(&view)
+        .with_external_context(U256::ZERO)
+        .with_spec_id(spec_id)
+        .with_block_env(block_env.clone())
+        .with_tx_env(tx_env)
+        .append_handler_register(defer_beneficiary_reward)
+        .append_handler_register_box(precompiles.handler_register_with_consensus_data(
+            block_env.number.saturating_to(),
+            consensus_data.cloned(),
+        ))
+        .build();
+
+    let result = evm.transact();
+    let fee = evm.context.external;
+    drop(evm);
+
+    Speculative {
+        result,
+        reads: view.reads.into_inner(),
+        fee,
+    }
+}
+
+/// Stores the fee owed to the coinbase in the external context instead of paying it, leaving
+/// it to the caller to credit it.
+pub fn defer_beneficiary_reward<DB: Database>(handler: &mut EvmHandler<'_, U256, DB>) {
+    let spec_id = handler.cfg.spec_id;
+
+    handler.post_execution.reward_beneficiary = Arc::new(move |context, gas| {
+        let env = &context.evm.env;
+        let effective_gas_price = env.effective_gas_price();
+
+        let coinbase_gas_price = if SpecId::enabled(spec_id, SpecId::LONDON) {
+            effective_gas_price.saturating_sub(env.block.basefee)
+        } else {
+            effective_gas_price
+        };
+
+        context.external = coinbase_gas_price * U256::from(gas.spent() - gas.refunded() as u64);
+
+        Ok(())
+    });
+}
+
+/// Executes calls from the protocol: the caller is neither loaded nor charged, its balance and
+/// nonce are not checked and its nonce is not incremented. The coinbase is not rewarded.
+pub fn system_call_handler<EXT, DB: Database>(handler: &mut EvmHandler<'_, EXT, DB>) {
+    handler.validation.tx_against_state = Arc::new(|_| Ok(()));
+    handler.pre_execution.deduct_caller = Arc::new(|_| Ok(()));
+    handler.post_execution.reimburse_caller = Arc::new(|_, _| Ok(()));
+    handler.post_execution.reward_beneficiary = Arc::new(|_, _| Ok(()));
+}
+
+// Reads the pending commit as it was before the batch, recording every location read.
+struct SpeculativeView<'a> {
+    state: PendingStateRef<'a, &'a DbReader>,
+    reads: RefCell<HashSet<Location>>,
+}
+
+impl DatabaseRef for SpeculativeView<'_> {
+    type Error = Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        self.reads.borrow_mut().insert(Location::Account(address));
+        self.state.basic_ref(address)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.reads.borrow_mut().insert(Location::Code(code_hash));
+        self.state.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.reads
+            .borrow_mut()
+            .insert(Location::Storage(address, index));
+        self.state.storage_ref(address, index)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.state.block_hash_ref(number)
+    }
+}
+
+// Locations changed by the transactions applied so far.
+#[derive(Default)]
+struct WriteSet {
+    accounts: HashSet<Address>,
+    storage: HashSet<(Address, U256)>,
+    // Accounts that were created or destroyed, all of their storage counts as written.
+    wiped: HashSet<Address>,
+}
+
+impl WriteSet {
+    fn conflicts_with(&self, reads: &HashSet<Location>) -> bool {
+        reads.iter().any(|location| match location {
+            Location::Account(address) => self.accounts.contains(address),
+            Location::Storage(address, index) => {
+                self.wiped.contains(address) || self.storage.contains(&(*address, *index))
+            }
+            // code is addressed by its hash and never changes
+            Location::Code(_) => false,
+        })
+    }
+
+    fn record(&mut self, result: &ResultAndState) {
+        for (address, account) in &result.state {
+            if !account.is_touched() {
+                continue;
+            }
+
+            self.accounts.insert(*address);
+
+            if account.is_created() || account.is_selfdestructed() {
+                self.wiped.insert(*address);
+            }
+
+            for (index, slot) in &account.storage {
+                if slot.is_changed() {
+                    self.storage.insert((*address, *index));
+                }
+            }
+        }
+    }
+}
+
+// Loads everything a speculative execution read into the state, so its cache ends up the same
+// as if the transaction had been executed on it.
+fn replay_reads(state: &mut CommitState, reads: &HashSet<Location>) -> Result<(), Error> {
+    for location in reads {
+        match location {
+            Location::Account(address) => {
+                state.load_cache_account(*address)?;
+            }
+            Location::Code(code_hash) => {
+                state.code_by_hash(*code_hash)?;
+            }
+            Location::Storage(..) => {}
+        }
+    }
+
+    // storage can only be read after its account was loaded
+    for location in reads {
+        if let Location::Storage(address, index) = location {
+            state.storage(*address, *index)?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Pays the fee deferred by [`defer_beneficiary_reward`], equivalent to the mainnet handler.
+///
+/// If the pending commit has a fee distribution, the fee is withheld from the coinbase and
+/// collected in the pending commit instead, see [`crate::state_commit::distribute_fees`].
+fn credit_fee(
+    state: &mut CommitState,
+    pending: &mut PendingCommit,
+    result: &mut ResultAndState,
+    coinbase: Address,
+    fee: U256,
+) -> Result<(), Error> {
+    if pending.fee_distribution.is_some() {
+        pending.fees += fee;
+        return Ok(());
+    }
+
+    let account = match result.state.entry(coinbase) {
+        std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+        std::collections::hash_map::Entry::Vacant(entry) => {
+            let account = match state.basic(coinbase)? {
+                Some(info) => Account::from(info),
+                None => Account::new_not_existing(),
+            };
+            entry.insert(account)
+        }
+    };
+
+    account.mark_touch();
+    account.info.balance = account.info.balance.saturating_add(fee);
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use revm::{
+        db::{states::StorageSlot, AccountStatus, TransitionAccount},
+        primitives::{address, bytes, Bytes, HashMap, TransactTo},
+    };
+
+    use super::*;
+    use crate::{db::CommitKey, receipt::TxPosition};
+
+    const COINBASE: Address = address!("00000000000000000000000000000000000000cb");
+
+    // sstore(0, sload(0) + 1)
+    const COUNTER: Bytes = bytes!("60005460010160005500");
+    // sstore(caller, 1)
+    const REGISTRY: Bytes = bytes!("6001335500");
+    // revert(0, 0)
+    const REVERTER: Bytes = bytes!("60006000fd");
+    // sstore(0, blockhash(0))
+    const BLOCK_HASH: Bytes = bytes!("60004060005500");
+
+    fn account(i: u64) -> Address {
+        Address::left_padding_from(&(0x1000 + i).to_be_bytes())
+    }
+
+    fn contract(code: &Bytes) -> Address {
+        Address::from_word(revm::primitives::keccak256(code))
+    }
+
+    fn setup(funded: u64) -> (tempfile::TempDir, PersistentDB) {
+        let path = tempfile::Builder::new()
+            .prefix("evm.mdb")
+            .tempdir()
+            .unwrap();
+        let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+        let mut transitions = HashMap::default();
+        for i in 0..funded {
+            transitions.insert(
+                account(i),
+                TransitionAccount {
+                    status: AccountStatus::InMemoryChange,
+                    info: Some(AccountInfo {
+                        balance: U256::from(1_000_000_000_000_000_000u128),
+                        ..Default::default()
+                    }),
+                    previous_status: AccountStatus::Loaded,
+                    previous_info: None,
+                    storage: Default::default(),
+                    storage_was_destroyed: false,
+                },
+            );
+        }
+
+        for code in [COUNTER, REGISTRY, REVERTER, BLOCK_HASH] {
+            let bytecode = Bytecode::new_raw(code.clone());
+            let mut storage = HashMap::default();
+            if code == COUNTER {
+                storage.insert(
+                    U256::ZERO,
+                    StorageSlot::new_changed(U256::ZERO, U256::from(5)),
+                );
+            }
+
+            transitions.insert(
+                contract(&code),
+                TransitionAccount {
+                    status: AccountStatus::InMemoryChange,
+                    info: Some(AccountInfo {
+                        code_hash: bytecode.hash_slow(),
+                        code: Some(bytecode),
+                        ..Default::default()
+                    }),
+                    previous_status: AccountStatus::Loaded,
+                    previous_info: None,
+                    storage,
+                    storage_was_destroyed: false,
+                },
+            );
+        }
+
+        crate::state_commit::commit_to_db(
+            &mut db,
+            PendingCommit {
+                key: CommitKey(0, 0),
+                transitions: TransitionState { transitions },
+                ..Default::default()
+            },
+        )
+        .expect("commit");
+
+        (path, db)
+    }
+
+    fn tx(caller: Address, to: Address, value: u64, nonce: u64, data: Bytes) -> Transaction {
+        let mut tx_hash = [0; 32];
+        tx_hash[..20].copy_from_slice(caller.as_slice());
+        tx_hash[24..].copy_from_slice(&nonce.to_be_bytes());
+
+        Transaction {
+            tx_hash: Some(B256::from(tx_hash)),
+            env: TxEnv {
+                caller,
+                transact_to: TransactTo::Call(to),
+                value: U256::from(value),
+                nonce: Some(nonce),
+                gas_limit: 100_000,
+                gas_price: U256::from(7),
+                data,
+                ..Default::default()
+            },
+        }
+    }
+
+    fn transfer(from: u64, to: Address, value: u64, nonce: u64) -> Transaction {
+        tx(account(from), to, value, nonce, Default::default())
+    }
+
+    fn call(from: u64, code: &Bytes, nonce: u64) -> Transaction {
+        tx(account(from), contract(code), 0, nonce, Default::default())
+    }
+
+    // Runs both engines on the same database and asserts they agree, returns the number of
+    // re-executed transactions of the parallel run.
+    fn assert_deterministic(db: &mut PersistentDB, transactions: Vec<Transaction>) -> usize {
+        assert_deterministic_with_gas_limit(db, 30_000_000, transactions)
+    }
+
+    fn assert_deterministic_with_gas_limit(
+        db: &mut PersistentDB,
+        block_gas_limit: u64,
+        transactions: Vec<Transaction>,
+    ) -> usize {
+        assert_deterministic_on(
+            db,
+            block_gas_limit,
+            PendingCommit::new(CommitKey(1, 0)),
+            transactions,
+        )
+        .0
+    }
+
+    // Same as `assert_deterministic` on top of the given pending commit, also returns the
+    // pending commit after the sequential run.
+    fn assert_deterministic_on(
+        db: &mut PersistentDB,
+        block_gas_limit: u64,
+        pending: PendingCommit,
+        transactions: Vec<Transaction>,
+    ) -> (usize, PendingCommit) {
+        let block_env = BlockEnv {
+            number: U256::from(1),
+            coinbase: COINBASE,
+            gas_limit: U256::from(block_gas_limit),
+            ..Default::default()
+        };
+
+        let mut sequential = pending.clone();
+        let expected = execute_sequential(
+            db,
+            &mut sequential,
+            &block_env,
+            SpecId::SHANGHAI,
+            &Default::default(),
+            None,
+            transactions.clone(),
+        );
+
+        let mut parallel = pending;
+        let actual = execute_parallel(
+            db,
+            &mut parallel,
+            &block_env,
+            SpecId::SHANGHAI,
+            &Default::default(),
+            None,
+            transactions,
+        );
+
+        assert_eq!(expected.outcomes.len(), actual.outcomes.len());
+        for (i, (expected, actual)) in expected.outcomes.iter().zip(&actual.outcomes).enumerate() {
+            match (expected, actual) {
+                (Ok(expected), Ok(actual)) => assert_eq!(expected, actual, "tx {i}"),
+                (Err(expected), Err(actual)) => {
+                    assert_eq!(expected.to_string(), actual.to_string(), "tx {i}")
+                }
+                _ => panic!("tx {i}: {expected:?} != {actual:?}"),
+            }
+        }
+
+        assert_eq!(sequential.results, parallel.results);
+        assert_eq!(sequential.gas_used, parallel.gas_used);
+        assert_eq!(sequential.positions, parallel.positions);
+        assert_eq!(sequential.transitions, parallel.transitions);
+        assert_eq!(sequential.cache.accounts, parallel.cache.accounts);
+        assert_eq!(sequential.fees, parallel.fees);
+
+        assert_eq!(
+            crate::state_hash::calculate(db, sequential.clone(), B256::ZERO).expect("hash"),
+            crate::state_hash::calculate(db, parallel.clone(), B256::ZERO).expect("hash"),
+        );
+
+        (actual.reexecuted, sequential)
+    }
+
+    #[test]
+    fn test_independent_transfers() {
+        let (_path, mut db) = setup(64);
+
+        let transactions = (0..32)
+            .map(|i| transfer(i, account(32 + i), 1_000 + i, 0))
+            .collect();
+
+        assert_eq!(assert_deterministic(&mut db, transactions), 0);
+    }
+
+    #[test]
+    fn test_dependent_transfers() {
+        let (_path, mut db) = setup(8);
+
+        let transactions = vec![
+            // chain of transfers through fresh accounts
+            transfer(0, account(100), 1_000_000, 0),
+            tx(account(100), account(101), 1, 0, Default::default()),
+            // same sender with consecutive nonces
+            transfer(1, account(2), 10, 0),
+            transfer(1, account(3), 10, 1),
+            transfer(1, account(4), 10, 2),
+            // receiver of an earlier transfer sends
+            transfer(2, account(5), 10, 0),
+            // value sent to the coinbase
+            transfer(6, COINBASE, 500, 0),
+            transfer(7, account(6), 500, 0),
+        ];
+
+        assert!(assert_deterministic(&mut db, transactions) > 0);
+    }
+
+    #[test]
+    fn test_contract_storage() {
+        let (_path, mut db) = setup(16);
+
+        let mut transactions = vec![];
+        for i in 0..8 {
+            // every call conflicts on the counter slot
+            transactions.push(call(i, &COUNTER, 0));
+            // while every caller writes its own registry slot
+            transactions.push(call(8 + i, &REGISTRY, 0));
+        }
+
+        assert!(assert_deterministic(&mut db, transactions) >= 7);
+    }
+
+    #[test]
+    fn test_failing_transactions() {
+        let (_path, mut db) = setup(8);
+
+        let transactions = vec![
+            transfer(0, account(1), 10, 0),
+            // nonce too high
+            transfer(2, account(3), 10, 5),
+            // nonce already used by the first transaction
+            transfer(0, account(3), 10, 0),
+            // unfunded sender
+            transfer(50, account(3), 10, 0),
+            call(4, &REVERTER, 0),
+            call(4, &REVERTER, 1),
+            transfer(5, account(4), 10, 0),
+        ];
+
+        assert_deterministic(&mut db, transactions);
+    }
+
+    #[test]
+    fn test_mixed_workload() {
+        let (_path, mut db) = setup(32);
+
+        let mut nonces = [0u64; 32];
+        let mut seed = 0x2545_f491_4f6c_dd1du64;
+        let mut next = |bound: u64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            seed % bound
+        };
+
+        let mut transactions = vec![];
+        for _ in 0..200 {
+            let from = next(32);
+            let nonce = nonces[from as usize];
+            nonces[from as usize] += 1;
+
+            transactions.push(match next(4) {
+                0 => call(from, &COUNTER, nonce),
+                1 => call(from, &REGISTRY, nonce),
+                _ => transfer(from, account(next(40)), next(1_000), nonce),
+            });
+        }
+
+        assert_deterministic(&mut db, transactions);
+    }
+
+    #[test]
+    fn test_block_hash() {
+        let (_path, mut db) = setup(2);
+
+        // read from the committed state as well as from the database reader
+        let transactions = vec![call(0, &BLOCK_HASH, 0), call(1, &BLOCK_HASH, 0)];
+        let (_, pending) =
+            assert_deterministic_on(&mut db, 30_000_000, PendingCommit::default(), transactions);
+
+        assert!(pending.results.values().all(ExecutionResult::is_success));
+        assert_eq!(
+            PendingStateRef {
+                db: &db,
+                cache: Some(&pending.cache),
+            }
+            .storage_ref(contract(&BLOCK_HASH), U256::ZERO)
+            .expect("storage"),
+            U256::ZERO
+        );
+    }
+
+    #[test]
+    fn test_deferred_fees() {
+        let (_path, mut db) = setup(8);
+
+        let transactions = vec![
+            transfer(0, account(1), 10, 0),
+            call(2, &COUNTER, 0),
+            call(3, &REVERTER, 0),
+            transfer(4, COINBASE, 10, 0),
+        ];
+
+        let pending = PendingCommit {
+            fee_distribution: crate::state_commit::FeeDistribution::new(5_000, 0, Address::ZERO),
+            ..PendingCommit::new(CommitKey(1, 0))
+        };
+        let (_, pending) = assert_deterministic_on(&mut db, 30_000_000, pending, transactions);
+
+        // the fees of all transactions are collected instead of paid to the coinbase
+        let gas_used: u64 = pending
+            .results
+            .values()
+            .map(ExecutionResult::gas_used)
+            .sum();
+        assert_eq!(pending.fees, U256::from(gas_used * 7));
+        assert_eq!(
+            PendingStateRef {
+                db: &db,
+                cache: Some(&pending.cache),
+            }
+            .basic_ref(COINBASE)
+            .expect("coinbase")
+            .map(|account| account.balance),
+            Some(U256::from(10))
+        );
+    }
+
+    #[test]
+    fn test_block_gas_limit() {
+        let (_path, mut db) = setup(8);
+
+        let small = |from, nonce| {
+            let mut tx = transfer(from, account(7), 10, nonce);
+            tx.env.gas_limit = 30_000;
+            tx
+        };
+
+        // each transfer reserves 100k gas but only uses 21k
+        let transactions = vec![
+            transfer(0, account(7), 10, 0),
+            transfer(1, account(7), 10, 0),
+            small(2, 0),
+            small(3, 0),
+            transfer(4, account(7), 10, 0),
+        ];
+
+        assert_deterministic_with_gas_limit(&mut db, 120_000, transactions.clone());
+
+        let mut pending = PendingCommit::new(CommitKey(1, 0));
+        let output = execute_sequential(
+            &db,
+            &mut pending,
+            &BlockEnv {
+                number: U256::from(1),
+                coinbase: COINBASE,
+                gas_limit: U256::from(120_000),
+                ..Default::default()
+            },
+            SpecId::SHANGHAI,
+            &Default::default(),
+            None,
+            transactions.clone(),
+        );
+
+        let rejected: Vec<_> = output
+            .outcomes
+            .iter()
+            .map(|outcome| {
+                matches!(
+                    outcome,
+                    Err(EVMError::Database(Error::BlockGasLimitExceeded { .. }))
+                )
+            })
+            .collect();
+        assert_eq!(rejected, vec![false, true, false, false, true]);
+
+        assert_eq!(pending.gas_used, 63_000);
+        let position = |tx: &Transaction| pending.positions[&tx.tx_hash.unwrap()];
+        assert_eq!(
+            position(&transactions[3]),
+            TxPosition {
+                index: 2,
+                cumulative_gas_used: 63_000
+            }
+        );
+    }
+}
@@ -0,0 +1,444 @@
+use std::collections::{HashMap, HashSet};
+
+use revm::primitives::{AccountInfo, Address, Bytecode, B256, KECCAK_EMPTY, U256};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    db::{AddressWrapper, ContractWrapper, Error, PersistentDB},
+    receipt::TxReceipt,
+    voters,
+};
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CommittedHeight {
+    pub height: u64,
+    pub accounts_hash: B256,
+    pub contracts_hash: B256,
+    pub storage_hash: B256,
+    pub randomness: B256,
+    pub gas_used: u64,
+    pub receipts: u64,
+    pub pruned: bool,
+}
+
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct IntegrityReport {
+    pub accounts: u64,
+    pub contracts: u64,
+    pub storage_slots: u64,
+    pub commits: u64,
+    pub validator_sets: u64,
+    pub voters: u64,
+    pub errors: Vec<String>,
+}
+
+/// Plain copy of the account, contract and storage tables. Commit receipts, validator sets and
+/// the voter index are not included.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct StateDump {
+    pub accounts: Vec<(Address, AccountInfo)>,
+    pub contracts: Vec<(B256, Bytecode)>,
+    pub storage: Vec<(Address, Vec<(U256, U256)>)>,
+}
+
+impl PersistentDB {
+    pub fn storage_entries(&self, address: Address) -> Result<Vec<(U256, U256)>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let mut entries = Vec::new();
+        if let Some(dups) = inner
+            .storage
+            .get_duplicates(&rtxn, &AddressWrapper(address))?
+        {
+            for dup in dups {
+                entries.push(dup?.1);
+            }
+        }
+
+        entries.sort_unstable_by_key(|(index, _)| *index);
+
+        Ok(entries)
+    }
+
+    pub fn committed_heights(
+        &self,
+        from: u64,
+        limit: usize,
+    ) -> Result<Vec<CommittedHeight>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let mut heights = Vec::new();
+        for entry in inner.commits.range(&rtxn, &(from..))?.take(limit) {
+            let (height, receipts) = entry?;
+            heights.push(CommittedHeight {
+                height,
+                accounts_hash: receipts.accounts_hash,
+                contracts_hash: receipts.contracts_hash,
+                storage_hash: receipts.storage_hash,
+                randomness: receipts.randomness,
+                gas_used: receipts.gas_used,
+                receipts: receipts.tx_receipts.len() as u64,
+                pruned: receipts.pruned,
+            });
+        }
+
+        Ok(heights)
+    }
+
+    /// Looks up a receipt without knowing its height, starting at the most recent commit.
+    pub fn find_receipt(&self, tx_hash: B256) -> Result<Option<(u64, TxReceipt)>, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        for entry in inner.commits.rev_iter(&rtxn)? {
+            let (height, mut receipts) = entry?;
+            if let Some(receipt) = receipts.tx_receipts.remove(&tx_hash) {
+                return Ok(Some((height, receipt)));
+            }
+        }
+
+        Ok(None)
+    }
+
+    /// Decodes every entry and checks references between tables. Problems are collected in the
+    /// report instead of aborting on the first one.
+    pub fn verify(&self) -> Result<IntegrityReport, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let mut report = IntegrityReport::default();
+
+        let mut code_hashes = HashSet::new();
+        for entry in inner.contracts.iter(&rtxn)? {
+            report.contracts += 1;
+
+            match entry {
+                Ok((ContractWrapper(hash), bytecode)) => {
+                    if bytecode.hash_slow() != hash {
+                        report
+                            .errors
+                            .push(format!("contract {hash} does not match its code hash"));
+                    }
+                    code_hashes.insert(hash);
+                }
+                Err(err) => report.errors.push(format!("contracts: {err}")),
+            }
+        }
+
+        for entry in inner.accounts.iter(&rtxn)? {
+            report.accounts += 1;
+
+            match entry {
+                Ok((AddressWrapper(address), account)) => {
+                    if account.code_hash != KECCAK_EMPTY
+                        && !code_hashes.contains(&account.code_hash)
+                    {
+                        report.errors.push(format!(
+                            "account {address} references missing code {}",
+                            account.code_hash
+                        ));
+                    }
+                }
+                Err(err) => report.errors.push(format!("accounts: {err}")),
+            }
+        }
+
+        let mut storage_indexes = HashMap::<Address, HashSet<U256>>::new();
+        for entry in inner.storage.iter(&rtxn)? {
+            report.storage_slots += 1;
+
+            match entry {
+                Ok((AddressWrapper(address), (index, value))) => {
+                    if value == U256::ZERO {
+                        report
+                            .errors
+                            .push(format!("storage {address}[{index}] stores zero value"));
+                    }
+
+                    if !storage_indexes.entry(address).or_default().insert(index) {
+                        report
+                            .errors
+                            .push(format!("storage {address}[{index}] has multiple values"));
+                    }
+                }
+                Err(err) => report.errors.push(format!("storage: {err}")),
+            }
+        }
+
+        let mut heights = HashSet::new();
+        for entry in inner.commits.iter(&rtxn)? {
+            report.commits += 1;
+
+            match entry {
+                Ok((height, receipts)) => {
+                    if receipts.pruned && !receipts.tx_receipts.is_empty() {
+                        report
+                            .errors
+                            .push(format!("commit {height} is pruned but has receipts"));
+                    }
+                    heights.insert(height);
+                }
+                Err(err) => report.errors.push(format!("commits: {err}")),
+            }
+        }
+
+        for entry in inner.validator_sets.iter(&rtxn)? {
+            report.validator_sets += 1;
+
+            match entry {
+                Ok((height, validator_set)) => {
+                    if validator_set.height != height {
+                        report.errors.push(format!(
+                            "validator set {height} is stored for height {}",
+                            validator_set.height
+                        ));
+                    }
+
+                    if !heights.contains(&height) {
+                        report
+                            .errors
+                            .push(format!("validator set {height} has no commit"));
+                    }
+
+                    let mut validators = HashSet::new();
+                    for entry in &validator_set.validators {
+                        if !validators.insert(entry.address) {
+                            report.errors.push(format!(
+                                "validator set {height} lists {} multiple times",
+                                entry.address
+                            ));
+                        }
+                    }
+                }
+                Err(err) => report.errors.push(format!("validator_sets: {err}")),
+            }
+        }
+
+        for entry in inner.voters.iter(&rtxn)? {
+            report.voters += 1;
+
+            match entry {
+                Ok((AddressWrapper(voter), validator)) => {
+                    if validator == Address::ZERO {
+                        report
+                            .errors
+                            .push(format!("voter {voter} votes for the zero address"));
+                    }
+                }
+                Err(err) => report.errors.push(format!("voters: {err}")),
+            }
+        }
+
+        Ok(report)
+    }
+
+    pub fn export_state(&self) -> Result<StateDump, Error> {
+        let rtxn = self.env.read_txn()?;
+        let inner = self.inner.borrow();
+
+        let mut dump = StateDump::default();
+
+        for entry in inner.accounts.iter(&rtxn)? {
+            let (AddressWrapper(address), account) = entry?;
+            dump.accounts.push((address, account));
+        }
+
+        for entry in inner.contracts.iter(&rtxn)? {
+            let (ContractWrapper(hash), bytecode) = entry?;
+            dump.contracts.push((hash, bytecode));
+        }
+
+        for entry in inner.storage.iter(&rtxn)? {
+            let (AddressWrapper(address), slot) = entry?;
+            match dump.storage.last_mut() {
+                Some((last, slots)) if *last == address => slots.push(slot),
+                _ => dump.storage.push((address, vec![slot])),
+            }
+        }
+
+        Ok(dump)
+    }
+
+    /// Writes a previously exported state into an empty database.
+    ///
+    /// The dump carries no votes, so the database is marked as lacking a complete voter index.
+    pub fn import_state(&self, dump: StateDump) -> Result<(), Error> {
+        let mut rwtxn = self.env.write_txn()?;
+        let inner = self.inner.borrow();
+
+        if !inner.accounts.is_empty(&rwtxn)?
+            || !inner.contracts.is_empty(&rwtxn)?
+            || !inner.storage.is_empty(&rwtxn)?
+        {
+            return Err(Error::NotEmpty);
+        }
+
+        for (address, account) in dump.accounts {
+            inner
+                .accounts
+                .put(&mut rwtxn, &AddressWrapper(address), &account)?;
+        }
+
+        for (hash, bytecode) in dump.contracts {
+            inner
+                .contracts
+                .put(&mut rwtxn, &ContractWrapper(hash), &bytecode)?;
+        }
+
+        for (address, slots) in dump.storage {
+            let address = AddressWrapper(address);
+            for slot in slots.into_iter().filter(|(_, value)| *value != U256::ZERO) {
+                inner.storage.put(&mut rwtxn, &address, &slot)?;
+            }
+        }
+
+        inner.metadata.delete(&mut rwtxn, voters::VOTER_INDEX_KEY)?;
+
+        rwtxn.commit()?;
+
+        Ok(())
+    }
+}
+
+#[test]
+fn test_export_import_state() {
+    use revm::{CacheState, TransitionState};
+
+    use crate::db::{CommitKey, PendingCommit, ValidatorSet};
+
+    let address = revm::primitives::address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    let code = Bytecode::new_raw(revm::primitives::bytes!("6080604052"));
+
+    let mut storage = HashMap::new();
+    for i in 1..4 {
+        storage.insert(
+            U256::from(i),
+            revm::db::states::StorageSlot::new_changed(U256::ZERO, U256::from(i * 10)),
+        );
+    }
+
+    let mut transitions = HashMap::new();
+    transitions.insert(
+        address,
+        revm::db::TransitionAccount {
+            status: revm::db::AccountStatus::InMemoryChange,
+            info: Some(AccountInfo {
+                balance: U256::from(100),
+                nonce: 1,
+                code_hash: code.hash_slow(),
+                code: Some(code.clone()),
+            }),
+            previous_status: revm::db::AccountStatus::Loaded,
+            previous_info: None,
+            storage,
+            storage_was_destroyed: false,
+        },
+    );
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    crate::state_commit::commit_to_db(
+        &mut db,
+        PendingCommit {
+            key: CommitKey(0, 0),
+            cache: CacheState::default(),
+            results: Default::default(),
+            transitions: TransitionState { transitions },
+            validator_set: Some(ValidatorSet {
+                height: 0,
+                round: 0,
+                validators: vec![Default::default()],
+            }),
+            ..Default::default()
+        },
+    )
+    .expect("ok");
+
+    let report = db.verify().expect("verify");
+    assert!(report.errors.is_empty(), "{:?}", report.errors);
+    assert_eq!(
+        (
+            report.accounts,
+            report.contracts,
+            report.storage_slots,
+            report.commits,
+            report.validator_sets,
+            report.voters
+        ),
+        (1, 1, 3, 1, 1, 0)
+    );
+
+    assert_eq!(db.committed_heights(0, 10).expect("heights").len(), 1);
+    assert_eq!(
+        db.storage_entries(address).expect("storage"),
+        (1..4)
+            .map(|i| (U256::from(i), U256::from(i * 10)))
+            .collect::<Vec<_>>()
+    );
+
+    // round trip through json
+    let dump = db.export_state().expect("export");
+    let json = serde_json::to_string(&dump).expect("json");
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let imported = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    imported
+        .import_state(serde_json::from_str(&json).expect("json"))
+        .expect("import");
+
+    assert!(imported.verify().expect("verify").errors.is_empty());
+    assert!(!imported.has_voter_index().expect("voter index"));
+    assert_eq!(
+        imported.export_state().expect("export").accounts,
+        dump.accounts
+    );
+    assert_eq!(
+        imported.storage_entries(address).expect("storage"),
+        db.storage_entries(address).expect("storage")
+    );
+
+    // refuses to import into a non-empty db
+    assert!(matches!(db.import_state(dump), Err(Error::NotEmpty)));
+
+    // reports entries of the validator set and voter tables that do not fit the rest
+    {
+        let mut rwtxn = db.env.write_txn().unwrap();
+        let inner = db.inner.borrow();
+        inner
+            .validator_sets
+            .put(
+                &mut rwtxn,
+                &5,
+                &ValidatorSet {
+                    height: 4,
+                    ..Default::default()
+                },
+            )
+            .unwrap();
+        inner
+            .voters
+            .put(&mut rwtxn, &AddressWrapper(address), &Address::ZERO)
+            .unwrap();
+        rwtxn.commit().unwrap();
+    }
+
+    let report = db.verify().expect("verify");
+    assert_eq!((report.validator_sets, report.voters), (2, 1));
+    assert_eq!(
+        report.errors,
+        vec![
+            "validator set 5 is stored for height 4".to_string(),
+            "validator set 5 has no commit".to_string(),
+            format!("voter {address} votes for the zero address"),
+        ]
+    );
+}
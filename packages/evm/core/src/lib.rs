@@ -1,6 +1,14 @@
 pub mod db;
 mod events;
+pub mod execution;
+pub mod inspect;
+pub mod migrations;
+pub mod precompiles;
+pub mod pruning;
 pub mod receipt;
 pub mod state_changes;
 pub mod state_commit;
 pub mod state_hash;
+pub mod system_call;
+pub mod validation;
+pub mod voters;
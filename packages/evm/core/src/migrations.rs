@@ -0,0 +1,456 @@
+//! Versioned upgrades of the serialized `commits` table, see [`SCHEMA_VERSION`]. Other tables
+//! are not versioned.
+
+use std::collections::HashMap;
+
+use heed::{types::SerdeBincode, RwTxn};
+use revm::primitives::{Bytes, Log, B256};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+
+use crate::{
+    db::{CommitReceipts, Error, InnerStorage},
+    receipt::TxReceipt,
+};
+
+/// Schema version of the `commits` table written by this build. Bump it and append a
+/// `Migration` whenever the serialized layout of `CommitReceipts` changes.
+///
+/// Only `CommitReceipts` is versioned. The `accounts` and `contracts` tables hold revm's
+/// `AccountInfo` and `Bytecode` encoded with bincode, so a revm upgrade that changes their layout
+/// is not caught by the version check and needs a migration of those tables of its own.
+pub const SCHEMA_VERSION: u64 = 5;
+
+pub(crate) const SCHEMA_VERSION_KEY: &str = "schema_version";
+
+// How often a migration reports progress while walking a table.
+const PROGRESS_INTERVAL: u64 = 10_000;
+
+// Layout of `TxReceipt` up to v3, before the position in the commit was stored.
+#[derive(Serialize, Deserialize)]
+struct TxReceiptV1 {
+    gas_used: u64,
+    gas_refunded: u64,
+    success: bool,
+    deployed_contract_address: Option<String>,
+    logs: Option<Vec<Log>>,
+    output: Option<Bytes>,
+}
+
+// Layout of `CommitReceipts` up to v1, before pruning was introduced.
+#[derive(Serialize, Deserialize)]
+struct CommitReceiptsV1 {
+    accounts_hash: B256,
+    storage_hash: B256,
+    contracts_hash: B256,
+    tx_receipts: HashMap<B256, TxReceiptV1>,
+}
+
+// Layout of `CommitReceipts` in v2, before the randomness of each height was stored.
+#[derive(Serialize, Deserialize)]
+struct CommitReceiptsV2 {
+    accounts_hash: B256,
+    storage_hash: B256,
+    contracts_hash: B256,
+    tx_receipts: HashMap<B256, TxReceiptV1>,
+    pruned: bool,
+}
+
+// Layout of `CommitReceipts` in v3, before gas was accounted per commit.
+#[derive(Serialize, Deserialize)]
+struct CommitReceiptsV3 {
+    accounts_hash: B256,
+    storage_hash: B256,
+    contracts_hash: B256,
+    randomness: B256,
+    tx_receipts: HashMap<B256, TxReceiptV1>,
+    pruned: bool,
+}
+
+// Layout of `CommitReceipts` in v4, before penalties were stored.
+#[derive(Serialize, Deserialize)]
+struct CommitReceiptsV4 {
+    accounts_hash: B256,
+    storage_hash: B256,
+    contracts_hash: B256,
+    randomness: B256,
+    gas_used: u64,
+    tx_receipts: HashMap<B256, TxReceipt>,
+    pruned: bool,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct MigrationProgress {
+    pub from: u64,
+    pub to: u64,
+    pub description: &'static str,
+    pub processed: u64,
+    pub total: u64,
+}
+
+type MigrationFn = fn(&InnerStorage, &mut RwTxn, &mut dyn FnMut(u64, u64)) -> Result<(), Error>;
+
+struct Migration {
+    // Version the migration upgrades from, it always upgrades to `from + 1`.
+    from: u64,
+    description: &'static str,
+    run: MigrationFn,
+}
+
+const MIGRATIONS: &[Migration] = &[
+    Migration {
+        from: 0,
+        description: "verify unversioned commit receipts",
+        run: migrate_v0_to_v1,
+    },
+    Migration {
+        from: 1,
+        description: "add pruned flag to commit receipts",
+        run: migrate_v1_to_v2,
+    },
+    Migration {
+        from: 2,
+        description: "add randomness to commit receipts",
+        run: migrate_v2_to_v3,
+    },
+    Migration {
+        from: 3,
+        description: "add gas used and transaction positions to commit receipts",
+        run: migrate_v3_to_v4,
+    },
+    Migration {
+        from: 4,
+        description: "add penalties to commit receipts",
+        run: migrate_v4_to_v5,
+    },
+];
+
+/// Brings the database up to `SCHEMA_VERSION`, applying each migration in its own write
+/// transaction so an interrupted upgrade resumes from the last completed version.
+///
+/// Returns the schema version of the database after all migrations ran.
+pub(crate) fn run(
+    env: &heed::Env,
+    inner: &InnerStorage,
+    progress: &mut dyn FnMut(MigrationProgress),
+) -> Result<u64, Error> {
+    let rtxn = env.read_txn()?;
+    let stored_version = inner.metadata.get(&rtxn, SCHEMA_VERSION_KEY)?;
+    let is_empty = inner.accounts.is_empty(&rtxn)? && inner.commits.is_empty(&rtxn)?;
+    drop(rtxn);
+
+    let mut version = match stored_version {
+        Some(version) => version,
+        // a fresh database always starts out with the current layout
+        None if is_empty => {
+            let mut wtxn = env.write_txn()?;
+            inner
+                .metadata
+                .put(&mut wtxn, SCHEMA_VERSION_KEY, &SCHEMA_VERSION)?;
+            wtxn.commit()?;
+            return Ok(SCHEMA_VERSION);
+        }
+        // databases created before versioning was introduced
+        None => 0,
+    };
+
+    if version > SCHEMA_VERSION {
+        return Err(Error::UnsupportedSchemaVersion {
+            found: version,
+            supported: SCHEMA_VERSION,
+        });
+    }
+
+    while version < SCHEMA_VERSION {
+        let migration = MIGRATIONS
+            .iter()
+            .find(|m| m.from == version)
+            .expect("migration for every version below SCHEMA_VERSION");
+
+        let mut report = |processed, total| {
+            progress(MigrationProgress {
+                from: migration.from,
+                to: migration.from + 1,
+                description: migration.description,
+                processed,
+                total,
+            })
+        };
+
+        let mut wtxn = env.write_txn()?;
+        (migration.run)(inner, &mut wtxn, &mut report)?;
+        inner
+            .metadata
+            .put(&mut wtxn, SCHEMA_VERSION_KEY, &(version + 1))?;
+        wtxn.commit()?;
+
+        version += 1;
+    }
+
+    Ok(version)
+}
+
+// The v1 layout is identical to the unversioned one, so it is sufficient to ensure every
+// commit receipt still decodes before the database is stamped.
+fn migrate_v0_to_v1(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+) -> Result<(), Error> {
+    let commits = inner
+        .commits
+        .remap_data_type::<SerdeBincode<CommitReceiptsV1>>();
+
+    let total = commits.len(wtxn)?;
+    let mut processed = 0;
+
+    for entry in commits.iter(wtxn)? {
+        entry?;
+        processed += 1;
+
+        if processed % PROGRESS_INTERVAL == 0 {
+            report(processed, total);
+        }
+    }
+
+    report(processed, total);
+
+    Ok(())
+}
+
+fn migrate_v1_to_v2(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+) -> Result<(), Error> {
+    rewrite_commits(inner, wtxn, report, |receipts: CommitReceiptsV1| {
+        CommitReceiptsV2 {
+            accounts_hash: receipts.accounts_hash,
+            storage_hash: receipts.storage_hash,
+            contracts_hash: receipts.contracts_hash,
+            tx_receipts: receipts.tx_receipts,
+            pruned: false,
+        }
+    })
+}
+
+// Heights committed before randomness was provided by consensus used zero.
+fn migrate_v2_to_v3(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+) -> Result<(), Error> {
+    rewrite_commits(inner, wtxn, report, |receipts: CommitReceiptsV2| {
+        CommitReceiptsV3 {
+            accounts_hash: receipts.accounts_hash,
+            storage_hash: receipts.storage_hash,
+            contracts_hash: receipts.contracts_hash,
+            randomness: B256::ZERO,
+            tx_receipts: receipts.tx_receipts,
+            pruned: receipts.pruned,
+        }
+    })
+}
+
+// The order of transactions was not stored, so their positions are left at zero. The gas used
+// by the commit is the sum of its receipts, which is zero for pruned heights.
+fn migrate_v3_to_v4(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+) -> Result<(), Error> {
+    rewrite_commits(inner, wtxn, report, |receipts: CommitReceiptsV3| {
+        let tx_receipts: HashMap<B256, TxReceipt> = receipts
+            .tx_receipts
+            .into_iter()
+            .map(|(tx_hash, receipt)| {
+                (
+                    tx_hash,
+                    TxReceipt {
+                        gas_used: receipt.gas_used,
+                        gas_refunded: receipt.gas_refunded,
+                        success: receipt.success,
+                        deployed_contract_address: receipt.deployed_contract_address,
+                        logs: receipt.logs,
+                        output: receipt.output,
+                        ..Default::default()
+                    },
+                )
+            })
+            .collect();
+
+        CommitReceiptsV4 {
+            accounts_hash: receipts.accounts_hash,
+            storage_hash: receipts.storage_hash,
+            contracts_hash: receipts.contracts_hash,
+            randomness: receipts.randomness,
+            gas_used: tx_receipts.values().map(|receipt| receipt.gas_used).sum(),
+            tx_receipts,
+            pruned: receipts.pruned,
+        }
+    })
+}
+
+// Penalties of earlier heights were not recorded.
+fn migrate_v4_to_v5(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+) -> Result<(), Error> {
+    rewrite_commits(inner, wtxn, report, |receipts: CommitReceiptsV4| {
+        CommitReceipts {
+            accounts_hash: receipts.accounts_hash,
+            storage_hash: receipts.storage_hash,
+            contracts_hash: receipts.contracts_hash,
+            randomness: receipts.randomness,
+            gas_used: receipts.gas_used,
+            tx_receipts: receipts.tx_receipts,
+            pruned: receipts.pruned,
+            penalties: Vec::new(),
+        }
+    })
+}
+
+// Decodes every commit receipt with the layout `T` and writes it back with the layout `U`.
+fn rewrite_commits<T, U>(
+    inner: &InnerStorage,
+    wtxn: &mut RwTxn,
+    report: &mut dyn FnMut(u64, u64),
+    convert: impl Fn(T) -> U,
+) -> Result<(), Error>
+where
+    T: Serialize + DeserializeOwned + 'static,
+    U: Serialize + DeserializeOwned + 'static,
+{
+    let commits = inner.commits.remap_data_type::<SerdeBincode<T>>();
+    let rewritten = inner.commits.remap_data_type::<SerdeBincode<U>>();
+
+    let total = commits.len(wtxn)?;
+    let mut processed = 0;
+    let mut next_height = 0;
+
+    // rewrite in chunks since entries cannot be replaced while iterating
+    loop {
+        let mut chunk = Vec::with_capacity(PROGRESS_INTERVAL as usize);
+        for entry in commits.range(wtxn, &(next_height..))? {
+            chunk.push(entry?);
+            if chunk.len() == PROGRESS_INTERVAL as usize {
+                break;
+            }
+        }
+
+        let Some((last_height, _)) = chunk.last() else {
+            break;
+        };
+        next_height = last_height + 1;
+
+        for (height, receipts) in chunk {
+            rewritten.put(wtxn, &height, &convert(receipts))?;
+            processed += 1;
+        }
+
+        report(processed, total);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_migrate_unversioned_db() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = crate::db::PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let env = db.env.clone();
+
+    // simulate a database created before versioning was introduced
+    {
+        let inner = db.inner.borrow();
+        let mut wtxn = env.write_txn().unwrap();
+        inner
+            .metadata
+            .delete(&mut wtxn, SCHEMA_VERSION_KEY)
+            .unwrap();
+
+        let mut tx_receipts = HashMap::new();
+        tx_receipts.insert(
+            B256::repeat_byte(1),
+            TxReceiptV1 {
+                gas_used: 21_000,
+                gas_refunded: 0,
+                success: true,
+                deployed_contract_address: None,
+                logs: None,
+                output: None,
+            },
+        );
+
+        inner
+            .commits
+            .remap_data_type::<SerdeBincode<CommitReceiptsV1>>()
+            .put(
+                &mut wtxn,
+                &7,
+                &CommitReceiptsV1 {
+                    accounts_hash: B256::repeat_byte(2),
+                    storage_hash: B256::repeat_byte(3),
+                    contracts_hash: B256::repeat_byte(4),
+                    tx_receipts,
+                },
+            )
+            .unwrap();
+        wtxn.commit().unwrap();
+    }
+    drop(db);
+
+    let mut reports = vec![];
+    let db =
+        crate::db::PersistentDB::new_with_env_and_progress(env, Default::default(), |progress| {
+            reports.push(progress)
+        })
+        .expect("database");
+
+    assert_eq!(db.schema_version(), SCHEMA_VERSION);
+    assert_eq!(
+        reports
+            .iter()
+            .map(|r| (r.from, r.to, r.processed, r.total))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, 1, 1, 1),
+            (1, 2, 1, 1),
+            (2, 3, 1, 1),
+            (3, 4, 1, 1),
+            (4, 5, 1, 1)
+        ]
+    );
+
+    assert_eq!(
+        db.get_committed_hashes(7).expect("hashes"),
+        Some((
+            B256::repeat_byte(2),
+            B256::repeat_byte(4),
+            B256::repeat_byte(3)
+        ))
+    );
+    assert_eq!(
+        db.get_committed_receipt(7, B256::repeat_byte(1))
+            .expect("receipt")
+            .1
+            .map(|receipt| (receipt.gas_used, receipt.success, receipt.tx_index)),
+        Some((21_000, true, 0))
+    );
+    assert_eq!(
+        db.get_committed_gas_used(7).expect("gas used"),
+        Some(21_000)
+    );
+    assert_eq!(
+        db.get_committed_randomness(7).expect("randomness"),
+        Some(B256::ZERO)
+    );
+    assert_eq!(
+        db.get_committed_penalties(7).expect("penalties"),
+        Some(vec![])
+    );
+}
@@ -0,0 +1,164 @@
+use std::sync::Arc;
+
+use alloy_sol_types::{sol, SolCall};
+use revm::{
+    precompile::{
+        u64_to_address, Precompile, PrecompileError, PrecompileOutput, PrecompileResult,
+        PrecompileWithAddress,
+    },
+    primitives::{Address, Bytes, Env, StatefulPrecompile, U256},
+};
+
+sol! {
+    interface IConsensusData {
+        function round() external view returns (uint256);
+        function proposer() external view returns (address);
+        function activeValidators() external view returns (address[] memory);
+    }
+}
+
+pub const ADDRESS: u64 = 0x0a03;
+
+const BASE_GAS: u64 = 100;
+const WORD_GAS: u64 = 3;
+
+/// Consensus data of the block a transaction is executed in.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ConsensusData {
+    /// Round of the `CommitKey`
+    pub round: u64,
+    /// Validator proposing the block
+    pub proposer: Address,
+    /// Active validator set
+    pub validators: Vec<Address>,
+}
+
+/// Read-only precompile answering the calls of `IConsensusData` from the consensus data of
+/// the current block. Outside of a block, e.g. in view calls, there is no data and every call
+/// fails.
+pub(super) fn precompile(data: Option<ConsensusData>) -> PrecompileWithAddress {
+    PrecompileWithAddress(
+        u64_to_address(ADDRESS),
+        Precompile::Stateful(Arc::new(ConsensusDataPrecompile(data))),
+    )
+}
+
+struct ConsensusDataPrecompile(Option<ConsensusData>);
+
+impl StatefulPrecompile for ConsensusDataPrecompile {
+    fn call(&self, input: &Bytes, gas_limit: u64, _env: &Env) -> PrecompileResult {
+        let Some(data) = self.0.as_ref() else {
+            return Err(PrecompileError::other("consensus data is not available").into());
+        };
+
+        let output = match input.get(..4).and_then(|selector| selector.try_into().ok()) {
+            Some(IConsensusData::roundCall::SELECTOR) => {
+                IConsensusData::roundCall::abi_encode_returns(&(U256::from(data.round),))
+            }
+            Some(IConsensusData::proposerCall::SELECTOR) => {
+                IConsensusData::proposerCall::abi_encode_returns(&(data.proposer,))
+            }
+            Some(IConsensusData::activeValidatorsCall::SELECTOR) => {
+                IConsensusData::activeValidatorsCall::abi_encode_returns(
+                    &(data.validators.clone(),),
+                )
+            }
+            _ => return Err(PrecompileError::other("unknown selector").into()),
+        };
+
+        let gas_used = BASE_GAS + WORD_GAS * (output.len() as u64).div_ceil(32);
+        if gas_used > gas_limit {
+            return Err(PrecompileError::OutOfGas.into());
+        }
+
+        Ok(PrecompileOutput::new(gas_used, output.into()))
+    }
+}
+
+#[test]
+fn test_consensus_data() {
+    use revm::primitives::{address, ExecutionResult, Output};
+
+    let data = ConsensusData {
+        round: 3,
+        proposer: address!("0000000000000000000000000000000000000011"),
+        validators: vec![
+            address!("0000000000000000000000000000000000000011"),
+            address!("0000000000000000000000000000000000000022"),
+        ],
+    };
+
+    let mut registry = super::PrecompileRegistry::default();
+    registry.register_consensus_data(5);
+
+    let call = |height, consensus_data: Option<ConsensusData>, input: Vec<u8>| {
+        super::call_precompile_with_consensus_data(
+            &registry,
+            height,
+            consensus_data,
+            u64_to_address(ADDRESS),
+            input,
+        )
+    };
+    let output = |result: ExecutionResult| match result {
+        ExecutionResult::Success {
+            output: Output::Call(output),
+            ..
+        } => output,
+        result => panic!("unexpected result {result:?}"),
+    };
+
+    let round = output(call(
+        5,
+        Some(data.clone()),
+        IConsensusData::roundCall {}.abi_encode(),
+    ));
+    assert_eq!(
+        IConsensusData::roundCall::abi_decode_returns(&round, true)
+            .unwrap()
+            ._0,
+        U256::from(3)
+    );
+
+    let proposer = output(call(
+        5,
+        Some(data.clone()),
+        IConsensusData::proposerCall {}.abi_encode(),
+    ));
+    assert_eq!(
+        IConsensusData::proposerCall::abi_decode_returns(&proposer, true)
+            .unwrap()
+            ._0,
+        data.proposer
+    );
+
+    let validators = output(call(
+        6,
+        Some(data.clone()),
+        IConsensusData::activeValidatorsCall {}.abi_encode(),
+    ));
+    assert_eq!(
+        IConsensusData::activeValidatorsCall::abi_decode_returns(&validators, true)
+            .unwrap()
+            ._0,
+        data.validators
+    );
+
+    // unknown selector and calls without consensus data fail
+    assert!(matches!(
+        call(5, Some(data.clone()), vec![1, 2, 3, 4]),
+        ExecutionResult::Halt { .. }
+    ));
+    assert!(matches!(
+        call(5, None, IConsensusData::roundCall {}.abi_encode()),
+        ExecutionResult::Halt { .. }
+    ));
+
+    // before activation the address is a plain empty account
+    assert!(output(call(
+        4,
+        Some(data),
+        IConsensusData::roundCall {}.abi_encode()
+    ))
+    .is_empty());
+}
@@ -0,0 +1,87 @@
+use ed25519_dalek::{Signature, VerifyingKey};
+use revm::{
+    precompile::{
+        calc_linear_cost_u32, u64_to_address, Precompile, PrecompileError, PrecompileOutput,
+        PrecompileResult, PrecompileWithAddress,
+    },
+    primitives::{Bytes, B256},
+};
+
+/// Verifies an Ed25519 signature (RFC 8032, strict).
+///
+/// Input is `public key (32) | signature (64) | message`. Returns 1 as a 32 byte word if the
+/// signature is valid and empty output otherwise.
+pub const PRECOMPILE: PrecompileWithAddress =
+    PrecompileWithAddress(u64_to_address(ADDRESS), Precompile::Standard(verify));
+
+pub const ADDRESS: u64 = 0x0a02;
+
+const BASE_GAS: u64 = 2_000;
+const GAS_PER_WORD: u64 = 12;
+const HEADER_LENGTH: usize = 96;
+
+fn verify(input: &Bytes, gas_limit: u64) -> PrecompileResult {
+    let gas_used = calc_linear_cost_u32(input.len(), BASE_GAS, GAS_PER_WORD);
+    if gas_used > gas_limit {
+        return Err(PrecompileError::OutOfGas.into());
+    }
+
+    let output = if verify_signature(input) {
+        B256::with_last_byte(1).into()
+    } else {
+        Bytes::new()
+    };
+
+    Ok(PrecompileOutput::new(gas_used, output))
+}
+
+fn verify_signature(input: &[u8]) -> bool {
+    if input.len() < HEADER_LENGTH {
+        return false;
+    }
+
+    let (public_key, rest) = input.split_at(32);
+    let (signature, message) = rest.split_at(64);
+
+    let Ok(public_key) = VerifyingKey::try_from(public_key) else {
+        return false;
+    };
+
+    let Ok(signature) = Signature::from_slice(signature) else {
+        return false;
+    };
+
+    public_key.verify_strict(message, &signature).is_ok()
+}
+
+#[test]
+fn test_ed25519_verify() {
+    use revm::primitives::{hex, ExecutionResult, Output};
+
+    // test 2 of RFC 8032 section 7.1
+    let public_key = hex!("3d4017c3e843895a92b70aa74d1b7ebc9c982ccf2ec4968cc0cd55f12af4660c");
+    let signature = hex!(
+        "92a009a9f0d4cab8720e820b5f642540a2b27b5416503f8fb3762223ebdb69da"
+        "085ac1e43e15996e458f3613d0f11d8c387b2eaeb4302aeeb00d291612bb0c00"
+    );
+    let message = hex!("72");
+
+    let input = [public_key.as_slice(), &signature, &message].concat();
+    assert!(verify_signature(&input));
+
+    let mut tampered = input.clone();
+    *tampered.last_mut().unwrap() ^= 1;
+    assert!(!verify_signature(&tampered));
+    assert!(!verify_signature(&input[..HEADER_LENGTH - 1]));
+
+    let mut registry = super::PrecompileRegistry::default();
+    registry.register_signatures(None, Some(0));
+
+    match super::call_precompile(&registry, 0, u64_to_address(ADDRESS), input) {
+        ExecutionResult::Success {
+            output: Output::Call(output),
+            ..
+        } => assert_eq!(output, Bytes::from(B256::with_last_byte(1))),
+        result => panic!("unexpected result {result:?}"),
+    }
+}
@@ -0,0 +1,216 @@
+use std::sync::Arc;
+
+use revm::{
+    handler::register::HandleRegisterBox,
+    precompile::{bls12_381, u64_to_address, PrecompileSpecId, PrecompileWithAddress},
+    primitives::Address,
+    ContextPrecompiles, Database,
+};
+
+pub mod consensus;
+pub mod ed25519;
+pub mod schnorr;
+
+use consensus::ConsensusData;
+
+/// Precompiles added on top of the ones revm enables for a spec, each available from its
+/// activation height onwards.
+#[derive(Clone, Default)]
+pub struct PrecompileRegistry {
+    precompiles: Vec<(u64, PrecompileWithAddress)>,
+    consensus_data_height: Option<u64>,
+}
+
+impl std::fmt::Debug for PrecompileRegistry {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_map()
+            .entries(
+                self.precompiles
+                    .iter()
+                    .map(|(height, precompile)| (precompile.0, height))
+                    .chain(
+                        self.consensus_data_height
+                            .as_ref()
+                            .map(|height| (u64_to_address(consensus::ADDRESS), height)),
+                    ),
+            )
+            .finish()
+    }
+}
+
+impl PrecompileRegistry {
+    /// Makes `precompiles` available from `activation_height`. A precompile registered at an
+    /// address that already has one replaces it.
+    pub fn register(
+        &mut self,
+        activation_height: u64,
+        precompiles: impl IntoIterator<Item = PrecompileWithAddress>,
+    ) -> &mut Self {
+        self.precompiles.extend(
+            precompiles
+                .into_iter()
+                .map(|precompile| (activation_height, precompile)),
+        );
+        self
+    }
+
+    /// EIP-2537 BLS12-381 curve operations, available from `activation_height` regardless of
+    /// the spec.
+    pub fn register_bls12_381(&mut self, activation_height: u64) -> &mut Self {
+        self.register(activation_height, bls12_381::precompiles())
+    }
+
+    /// BIP-340 Schnorr and Ed25519 signature verification.
+    pub fn register_signatures(
+        &mut self,
+        schnorr_height: Option<u64>,
+        ed25519_height: Option<u64>,
+    ) -> &mut Self {
+        if let Some(height) = schnorr_height {
+            self.register(height, [schnorr::PRECOMPILE]);
+        }
+
+        if let Some(height) = ed25519_height {
+            self.register(height, [ed25519::PRECOMPILE]);
+        }
+
+        self
+    }
+
+    /// Round, proposer and active validator set of the current block, see
+    /// [`consensus::IConsensusData`].
+    pub fn register_consensus_data(&mut self, activation_height: u64) -> &mut Self {
+        self.consensus_data_height = Some(activation_height);
+        self
+    }
+
+    pub fn is_active(&self, address: &Address, height: u64) -> bool {
+        self.active(height)
+            .any(|precompile| precompile.0 == *address)
+            || (*address == u64_to_address(consensus::ADDRESS)
+                && self.consensus_data_active(height))
+    }
+
+    fn consensus_data_active(&self, height: u64) -> bool {
+        self.consensus_data_height
+            .is_some_and(|activation_height| height >= activation_height)
+    }
+
+    fn active(&self, height: u64) -> impl Iterator<Item = &PrecompileWithAddress> {
+        self.precompiles
+            .iter()
+            .filter(move |(activation_height, _)| height >= *activation_height)
+            .map(|(_, precompile)| precompile)
+    }
+
+    /// Handler register that loads the precompiles of the spec together with the ones active at
+    /// `height`.
+    pub fn handler_register<'a, EXT, DB: Database>(
+        &self,
+        height: u64,
+    ) -> HandleRegisterBox<'a, EXT, DB> {
+        self.handler_register_with_consensus_data(height, None)
+    }
+
+    /// Like [`Self::handler_register`], with the consensus data precompile answering from
+    /// `consensus_data` of the block being executed.
+    pub fn handler_register_with_consensus_data<'a, EXT, DB: Database>(
+        &self,
+        height: u64,
+        consensus_data: Option<ConsensusData>,
+    ) -> HandleRegisterBox<'a, EXT, DB> {
+        let mut active: Vec<_> = self.active(height).cloned().collect();
+        if self.consensus_data_active(height) {
+            active.push(consensus::precompile(consensus_data));
+        }
+
+        Box::new(move |handler| {
+            if active.is_empty() {
+                return;
+            }
+
+            let spec_id = handler.cfg.spec_id;
+            let active = active.clone();
+            handler.pre_execution.load_precompiles = Arc::new(move || {
+                let mut precompiles =
+                    ContextPrecompiles::new(PrecompileSpecId::from_spec_id(spec_id));
+                precompiles.extend(active.iter().cloned());
+                precompiles
+            });
+        })
+    }
+}
+
+#[cfg(test)]
+pub(crate) fn call_precompile(
+    registry: &PrecompileRegistry,
+    height: u64,
+    address: Address,
+    input: Vec<u8>,
+) -> revm::primitives::ExecutionResult {
+    call_precompile_with_consensus_data(registry, height, None, address, input)
+}
+
+#[cfg(test)]
+pub(crate) fn call_precompile_with_consensus_data(
+    registry: &PrecompileRegistry,
+    height: u64,
+    consensus_data: Option<ConsensusData>,
+    address: Address,
+    input: Vec<u8>,
+) -> revm::primitives::ExecutionResult {
+    use revm::{
+        db::EmptyDB,
+        primitives::{address, SpecId, TransactTo, TxEnv},
+        Evm,
+    };
+
+    let mut evm = Evm::builder()
+        .with_db(EmptyDB::default())
+        .with_spec_id(SpecId::SHANGHAI)
+        .with_tx_env(TxEnv {
+            caller: address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508"),
+            transact_to: TransactTo::Call(address),
+            data: input.into(),
+            gas_limit: 100_000,
+            ..Default::default()
+        })
+        .append_handler_register_box(
+            registry.handler_register_with_consensus_data(height, consensus_data),
+        )
+        .build();
+
+    evm.transact().expect("transact").result
+}
+
+#[test]
+fn test_bls12_381_activation() {
+    use revm::primitives::{address, hex, ExecutionResult, Output};
+
+    // G1 generator and the point at infinity in EIP-2537 encoding
+    let generator = hex!(
+        "0000000000000000000000000000000017f1d3a73197d7942695638c4fa9ac0fc3688c4f9774b905a14e3a3f171bac586c55e83ff97a1aeffb3af00adb22c6bb"
+        "0000000000000000000000000000000008b3f481e3aaa0f1a09e30ed741d8ae4fcf5e095d5d00af600db18cb2c04b3edd03cc744a2888ae40caa232946c5e7e1"
+    );
+    let infinity = [0u8; 128];
+
+    let mut registry = PrecompileRegistry::default();
+    registry.register_bls12_381(10);
+
+    let g1_add = address!("000000000000000000000000000000000000000b");
+    let input = [generator.as_slice(), infinity.as_slice()].concat();
+
+    // before activation the address is a plain empty account
+    assert!(matches!(
+        call_precompile(&registry, 9, g1_add, input.clone()),
+        ExecutionResult::Success { output: Output::Call(output), .. } if output.is_empty()
+    ));
+
+    assert!(matches!(
+        call_precompile(&registry, 10, g1_add, input),
+        ExecutionResult::Success { output: Output::Call(output), .. } if output[..] == generator[..]
+    ));
+
+    assert!(!registry.is_active(&g1_add, 9));
+    assert!(registry.is_active(&g1_add, 11));
+}
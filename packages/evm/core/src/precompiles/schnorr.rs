@@ -0,0 +1,95 @@
+use k256::schnorr::{signature::hazmat::PrehashVerifier, Signature, VerifyingKey};
+use revm::{
+    precompile::{
+        u64_to_address, Precompile, PrecompileError, PrecompileOutput, PrecompileResult,
+        PrecompileWithAddress,
+    },
+    primitives::{Bytes, B256},
+};
+
+/// Verifies a BIP-340 Schnorr signature.
+///
+/// Input is `message (32) | x-only public key (32) | signature (64)`. Returns 1 as a 32 byte
+/// word if the signature is valid and empty output otherwise.
+pub const PRECOMPILE: PrecompileWithAddress =
+    PrecompileWithAddress(u64_to_address(ADDRESS), Precompile::Standard(verify));
+
+pub const ADDRESS: u64 = 0x0a01;
+
+const GAS: u64 = 3_000;
+const INPUT_LENGTH: usize = 128;
+
+fn verify(input: &Bytes, gas_limit: u64) -> PrecompileResult {
+    if GAS > gas_limit {
+        return Err(PrecompileError::OutOfGas.into());
+    }
+
+    let output = if verify_signature(input) {
+        B256::with_last_byte(1).into()
+    } else {
+        Bytes::new()
+    };
+
+    Ok(PrecompileOutput::new(GAS, output))
+}
+
+fn verify_signature(input: &[u8]) -> bool {
+    if input.len() != INPUT_LENGTH {
+        return false;
+    }
+
+    let (message, rest) = input.split_at(32);
+    let (public_key, signature) = rest.split_at(32);
+
+    let Ok(public_key) = VerifyingKey::from_bytes(public_key) else {
+        return false;
+    };
+
+    let Ok(signature) = Signature::try_from(signature) else {
+        return false;
+    };
+
+    public_key.verify_prehash(message, &signature).is_ok()
+}
+
+#[test]
+fn test_schnorr_verify() {
+    use revm::primitives::{hex, ExecutionResult, Output};
+
+    // test vector 1 of BIP-340
+    let public_key = hex!("dff1d77f2a671c5f36183726db2341be58feae1da2deced843240f7b502ba659");
+    let message = hex!("243f6a8885a308d313198a2e03707344a4093822299f31d0082efa98ec4e6c89");
+    let signature = hex!(
+        "6896bd60eeae296db48a229ff71dfe071bde413e6d43f917dc8dcf8c78de3341"
+        "8906d11ac976abccb20b091292bff4ea897efcb639ea871cfa95f6de339e4b0a"
+    );
+
+    let input = [message.as_slice(), &public_key, &signature].concat();
+    assert!(verify_signature(&input));
+
+    let mut tampered = input.clone();
+    tampered[0] ^= 1;
+    assert!(!verify_signature(&tampered));
+    assert!(!verify_signature(&input[..INPUT_LENGTH - 1]));
+
+    let mut registry = super::PrecompileRegistry::default();
+    registry.register_signatures(Some(5), None);
+
+    let call = |height, input: Vec<u8>| match super::call_precompile(
+        &registry,
+        height,
+        u64_to_address(ADDRESS),
+        input,
+    ) {
+        ExecutionResult::Success {
+            output: Output::Call(output),
+            ..
+        } => output,
+        result => panic!("unexpected result {result:?}"),
+    };
+
+    assert_eq!(call(5, input.clone()), Bytes::from(B256::with_last_byte(1)));
+    assert!(call(5, tampered).is_empty());
+    // not active yet
+    assert!(call(4, input).is_empty());
+}
@@ -0,0 +1,215 @@
+use heed::RwTxn;
+
+use crate::db::{CommitReceipts, Error, InnerStorage};
+
+pub(crate) const PRUNE_CURSOR_KEY: &str = "prune_cursor";
+pub(crate) const PRUNED_HEIGHTS_KEY: &str = "pruned_heights";
+pub(crate) const PRUNED_RECEIPTS_KEY: &str = "pruned_receipts";
+
+// Upper bound of heights pruned in a single commit, so that enabling pruning on an existing
+// archive database catches up gradually instead of stalling one commit.
+const MAX_PRUNED_HEIGHTS_PER_COMMIT: usize = 1_000;
+
+/// Controls how long transaction receipts of committed heights are retained.
+///
+/// Pruning only drops the receipts, the state hashes of a height are always kept so
+/// `is_height_committed` and `get_committed_hashes` keep working.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PruningMode {
+    /// Keep receipts of every height forever.
+    #[default]
+    Archive,
+    /// Keep receipts of the last N heights (including the current one).
+    KeepLast(u64),
+    /// Never store receipts, only the state hashes.
+    HashesOnly,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct PruneStats {
+    pub heights: u64,
+    pub receipts: u64,
+}
+
+impl PruningMode {
+    // Heights strictly below the returned height may be pruned.
+    fn prune_below(&self, height: u64) -> Option<u64> {
+        match self {
+            PruningMode::Archive => None,
+            PruningMode::KeepLast(n) => Some(height.saturating_sub(n.saturating_sub(1))),
+            PruningMode::HashesOnly => Some(height),
+        }
+    }
+}
+
+/// Drops receipts of heights that fall outside the retention window of `mode` after
+/// committing `height`. Runs inside the commit's write transaction.
+pub(crate) fn prune(
+    inner: &InnerStorage,
+    rwtxn: &mut RwTxn,
+    mode: PruningMode,
+    height: u64,
+) -> Result<PruneStats, Error> {
+    let Some(prune_below) = mode.prune_below(height) else {
+        return Ok(Default::default());
+    };
+
+    let cursor = inner.metadata.get(rwtxn, PRUNE_CURSOR_KEY)?.unwrap_or(0);
+    if cursor >= prune_below {
+        return Ok(Default::default());
+    }
+
+    let mut candidates = Vec::new();
+    for entry in inner.commits.range(rwtxn, &(cursor..prune_below))? {
+        let (height, receipts) = entry?;
+        if !receipts.pruned {
+            candidates.push((height, receipts));
+        }
+
+        if candidates.len() == MAX_PRUNED_HEIGHTS_PER_COMMIT {
+            break;
+        }
+    }
+
+    let next_cursor = match candidates.last() {
+        Some((height, _)) if candidates.len() == MAX_PRUNED_HEIGHTS_PER_COMMIT => height + 1,
+        _ => prune_below,
+    };
+
+    let mut stats = PruneStats::default();
+    for (height, receipts) in candidates {
+        stats.heights += 1;
+        stats.receipts += receipts.tx_receipts.len() as u64;
+
+        inner.commits.put(
+            rwtxn,
+            &height,
+            &CommitReceipts {
+                tx_receipts: Default::default(),
+                pruned: true,
+                ..receipts
+            },
+        )?;
+    }
+
+    inner.metadata.put(rwtxn, PRUNE_CURSOR_KEY, &next_cursor)?;
+
+    if stats.heights > 0 {
+        let heights = inner.metadata.get(rwtxn, PRUNED_HEIGHTS_KEY)?.unwrap_or(0);
+        let receipts = inner.metadata.get(rwtxn, PRUNED_RECEIPTS_KEY)?.unwrap_or(0);
+        inner
+            .metadata
+            .put(rwtxn, PRUNED_HEIGHTS_KEY, &(heights + stats.heights))?;
+        inner
+            .metadata
+            .put(rwtxn, PRUNED_RECEIPTS_KEY, &(receipts + stats.receipts))?;
+    }
+
+    Ok(stats)
+}
+
+#[test]
+fn test_prune_below() {
+    assert_eq!(PruningMode::Archive.prune_below(10), None);
+    assert_eq!(PruningMode::KeepLast(0).prune_below(10), Some(10));
+    assert_eq!(PruningMode::KeepLast(1).prune_below(10), Some(10));
+    assert_eq!(PruningMode::KeepLast(3).prune_below(10), Some(8));
+    assert_eq!(PruningMode::KeepLast(100).prune_below(10), Some(0));
+    assert_eq!(PruningMode::HashesOnly.prune_below(10), Some(10));
+}
+
+#[cfg(test)]
+fn commit_with_receipts(db: &crate::db::PersistentDB, height: u64, receipts: u8) {
+    let mut commit = crate::state_commit::StateCommit {
+        key: crate::db::CommitKey(height, 0),
+        ..Default::default()
+    };
+
+    for i in 0..receipts {
+        commit.results.insert(
+            revm::primitives::B256::repeat_byte(i),
+            revm::primitives::ExecutionResult::Revert {
+                gas_used: 21000,
+                output: Default::default(),
+            },
+        );
+    }
+
+    db.commit(&mut commit).expect("commit");
+}
+
+#[test]
+fn test_prune_keep_last() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = crate::db::PersistentDB::new(path.path().to_path_buf()).expect("database");
+    db.set_pruning_mode(PruningMode::KeepLast(2));
+
+    for height in 0..5 {
+        commit_with_receipts(&db, height, 2);
+    }
+
+    let (last, total) = db.pruning_stats().expect("stats");
+    assert_eq!(
+        last,
+        PruneStats {
+            heights: 1,
+            receipts: 2
+        }
+    );
+    assert_eq!(
+        total,
+        PruneStats {
+            heights: 3,
+            receipts: 6
+        }
+    );
+
+    for height in 0..5 {
+        assert!(db.is_height_committed(height));
+        assert!(db.get_committed_hashes(height).expect("hashes").is_some());
+
+        let receipt = db.get_committed_receipt(height, revm::primitives::B256::repeat_byte(1));
+        if height < 3 {
+            assert!(matches!(receipt, Err(Error::ReceiptsPruned(h)) if h == height));
+        } else {
+            assert!(receipt.expect("receipt").1.is_some());
+        }
+    }
+}
+
+#[test]
+fn test_prune_hashes_only() {
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = crate::db::PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    // archive heights are caught up once pruning is enabled
+    commit_with_receipts(&db, 0, 3);
+    db.set_pruning_mode(PruningMode::HashesOnly);
+    commit_with_receipts(&db, 1, 3);
+
+    let (last, total) = db.pruning_stats().expect("stats");
+    assert_eq!(
+        last,
+        PruneStats {
+            heights: 1,
+            receipts: 3
+        }
+    );
+    assert_eq!(total, last);
+
+    for height in 0..2 {
+        assert!(db.is_height_committed(height));
+        assert!(matches!(
+            db.get_committed_receipt(height, revm::primitives::B256::repeat_byte(1)),
+            Err(Error::ReceiptsPruned(_))
+        ));
+    }
+}
@@ -1,4 +1,4 @@
-use revm::primitives::{Bytes, ExecutionResult, Log};
+use revm::primitives::{Bytes, ExecutionResult, Log, B256};
 use serde::{Deserialize, Serialize};
 
 #[derive(Default, Clone, Serialize, Deserialize)]
@@ -9,6 +9,27 @@ pub struct TxReceipt {
     pub deployed_contract_address: Option<String>,
     pub logs: Option<Vec<Log>>,
     pub output: Option<Bytes>,
+    /// Index of the transaction in its commit
+    pub tx_index: u64,
+    /// Gas used by the transaction and all transactions before it in the same commit
+    pub cumulative_gas_used: u64,
+}
+
+/// Position of a transaction in the commit it was executed in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct TxPosition {
+    pub index: u64,
+    pub cumulative_gas_used: u64,
+}
+
+impl TxReceipt {
+    pub fn with_position(self, position: TxPosition) -> Self {
+        Self {
+            tx_index: position.index,
+            cumulative_gas_used: position.cumulative_gas_used,
+            ..self
+        }
+    }
 }
 
 pub fn map_execution_result(result: ExecutionResult) -> TxReceipt {
@@ -27,6 +48,7 @@ pub fn map_execution_result(result: ExecutionResult) -> TxReceipt {
                 deployed_contract_address: None,
                 logs: Some(logs),
                 output: Some(output),
+                ..Default::default()
             },
             revm::primitives::Output::Create(output, address) => TxReceipt {
                 gas_used,
@@ -35,6 +57,7 @@ pub fn map_execution_result(result: ExecutionResult) -> TxReceipt {
                 deployed_contract_address: address.map(|address| address.to_string()),
                 logs: Some(logs),
                 output: Some(output),
+                ..Default::default()
             },
         },
         ExecutionResult::Revert { gas_used, output } => TxReceipt {
@@ -44,6 +67,7 @@ pub fn map_execution_result(result: ExecutionResult) -> TxReceipt {
             deployed_contract_address: None,
             logs: None,
             output: Some(output),
+            ..Default::default()
         },
         ExecutionResult::Halt { gas_used, .. } => TxReceipt {
             gas_used,
@@ -52,6 +76,35 @@ pub fn map_execution_result(result: ExecutionResult) -> TxReceipt {
             deployed_contract_address: None,
             logs: None,
             output: None,
+            ..Default::default()
         },
     }
 }
+
+/// Receipts of the system calls of a commit. System calls are positioned after the
+/// `transactions` of the commit and continue their cumulative gas used from `gas_used`, but
+/// their own gas is not part of the gas used by the commit.
+pub fn map_system_results(
+    results: &[(B256, ExecutionResult)],
+    transactions: u64,
+    gas_used: u64,
+) -> Vec<(B256, TxReceipt)> {
+    let mut cumulative_gas_used = gas_used;
+
+    results
+        .iter()
+        .enumerate()
+        .map(|(index, (tx_hash, result))| {
+            cumulative_gas_used += result.gas_used();
+            let position = TxPosition {
+                index: transactions + index as u64,
+                cumulative_gas_used,
+            };
+
+            (
+                *tx_hash,
+                map_execution_result(result.clone()).with_position(position),
+            )
+        })
+        .collect()
+}
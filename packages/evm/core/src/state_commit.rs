@@ -2,12 +2,20 @@ use std::collections::{BTreeMap, HashMap};
 
 use alloy_sol_types::SolEvent;
 use revm::{
-    db::WrapDatabaseRef,
-    primitives::{Address, ExecutionResult, B256},
+    db::{states::StorageSlot, WrapDatabaseRef},
+    primitives::{
+        alloy_primitives::U64, AccountInfo, Address, Bytecode, Bytes, ExecutionResult, B256,
+        KECCAK_EMPTY, U256,
+    },
+    DatabaseRef,
 };
+use serde::{Deserialize, Serialize};
 
 use crate::{
-    db::{CommitKey, Error, GenesisInfo, PendingCommit, PersistentDB},
+    db::{
+        CommitKey, Error, GenesisInfo, PendingCommit, PendingStateRef, PersistentDB, ValidatorSet,
+    },
+    receipt::TxPosition,
     state_changes::{self, AccountUpdate},
 };
 
@@ -16,6 +24,12 @@ pub struct StateCommit {
     pub key: CommitKey,
     pub change_set: state_changes::StateChangeset,
     pub results: BTreeMap<B256, ExecutionResult>,
+    pub randomness: B256,
+    pub gas_used: u64,
+    pub positions: BTreeMap<B256, TxPosition>,
+    pub validator_set: Option<ValidatorSet>,
+    pub system_results: Vec<(B256, ExecutionResult)>,
+    pub penalties: Vec<AppliedPenalty>,
 }
 
 pub fn build_commit(
@@ -31,6 +45,13 @@ pub fn build_commit(
         cache,
         results,
         transitions,
+        randomness,
+        gas_used,
+        positions,
+        validator_set,
+        system_results,
+        penalties,
+        ..
     } = pending_commit;
 
     let mut state_builder = revm::State::builder().with_cached_prestate(cache).build();
@@ -45,9 +66,62 @@ pub fn build_commit(
         key,
         change_set,
         results,
+        randomness,
+        gas_used,
+        positions,
+        validator_set,
+        system_results,
+        penalties,
     })
 }
 
+/// Balance credited by consensus at the end of a commit.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Reward {
+    pub address: Address,
+    pub amount: u128,
+    /// What the reward is for, e.g. a block reward or a voter share
+    pub kind: String,
+}
+
+/// Credits every entry of `rewards` and records them in the pending commit. Entries for the
+/// same address are added up.
+///
+/// Either all rewards are applied or, if any balance would overflow, none of them.
+pub fn apply_reward_schedule(
+    db: &mut PersistentDB,
+    pending: &mut PendingCommit,
+    rewards: Vec<Reward>,
+) -> Result<(), crate::db::Error> {
+    let mut totals = HashMap::<Address, u128>::new();
+    for reward in &rewards {
+        let total = totals.entry(reward.address).or_default();
+        *total = total
+            .checked_add(reward.amount)
+            .ok_or(Error::RewardOverflow(reward.address))?;
+    }
+
+    let state = PendingStateRef {
+        db: &*db,
+        cache: Some(&pending.cache),
+    };
+    for (address, total) in &totals {
+        let balance = state
+            .basic_ref(*address)?
+            .map(|account| account.balance)
+            .unwrap_or_default();
+
+        if balance.checked_add(U256::from(*total)).is_none() {
+            return Err(Error::RewardOverflow(*address));
+        }
+    }
+
+    apply_rewards(db, pending, totals)?;
+    pending.rewards.extend(rewards);
+
+    Ok(())
+}
+
 pub fn apply_rewards(
     db: &mut PersistentDB,
     pending: &mut PendingCommit,
@@ -74,6 +148,326 @@ pub fn apply_rewards(
     Ok(())
 }
 
+/// How [`apply_penalties`] handles a penalty exceeding the balance of its address.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PenaltyMode {
+    /// Deduct as much as the balance allows
+    #[default]
+    Saturating,
+    /// Reject all penalties
+    Strict,
+}
+
+/// Balance debited by consensus, e.g. for double signing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Penalty {
+    pub address: Address,
+    pub amount: U256,
+    /// Why the penalty is applied
+    pub reason: String,
+}
+
+/// A [`Penalty`] together with the amount that was actually deducted.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AppliedPenalty {
+    pub address: Address,
+    pub reason: String,
+    pub requested: U256,
+    pub deducted: U256,
+}
+
+/// Deducts every entry of `penalties` from the balance of its address and records them in the
+/// pending commit. Penalties for the same address are deducted in order from what is left.
+///
+/// In [`PenaltyMode::Strict`] either all penalties are applied or, if any exceeds the balance
+/// left, none of them.
+pub fn apply_penalties(
+    db: &mut PersistentDB,
+    pending: &mut PendingCommit,
+    penalties: Vec<Penalty>,
+    mode: PenaltyMode,
+) -> Result<(), crate::db::Error> {
+    let state = PendingStateRef {
+        db: &*db,
+        cache: Some(&pending.cache),
+    };
+
+    let mut balances = HashMap::<Address, U256>::new();
+    let mut applied = Vec::with_capacity(penalties.len());
+    for penalty in penalties {
+        let balance = match balances.entry(penalty.address) {
+            std::collections::hash_map::Entry::Occupied(entry) => entry.into_mut(),
+            std::collections::hash_map::Entry::Vacant(entry) => entry.insert(
+                state
+                    .basic_ref(penalty.address)?
+                    .map(|account| account.balance)
+                    .unwrap_or_default(),
+            ),
+        };
+
+        if mode == PenaltyMode::Strict && penalty.amount > *balance {
+            return Err(Error::InsufficientBalance {
+                address: penalty.address,
+                balance: *balance,
+                amount: penalty.amount,
+            });
+        }
+
+        let deducted = penalty.amount.min(*balance);
+        *balance -= deducted;
+
+        applied.push(AppliedPenalty {
+            address: penalty.address,
+            reason: penalty.reason,
+            requested: penalty.amount,
+            deducted,
+        });
+    }
+
+    let mut deductions = HashMap::<Address, U256>::new();
+    for penalty in &applied {
+        *deductions.entry(penalty.address).or_default() += penalty.deducted;
+    }
+
+    let mut state = revm::State::builder()
+        .with_bundle_update()
+        .with_cached_prestate(std::mem::take(&mut pending.cache))
+        .with_database(WrapDatabaseRef(&db))
+        .build();
+
+    let mut transitions = Vec::with_capacity(deductions.len());
+    for (address, amount) in deductions {
+        if amount.is_zero() {
+            continue;
+        }
+
+        let account = state.load_cache_account(address)?;
+        let mut info = account.account_info().unwrap_or_default();
+        info.balance -= amount;
+        transitions.push((address, account.change(info, Default::default())));
+    }
+
+    pending.transitions.add_transitions(transitions);
+    pending.cache = std::mem::take(&mut state.cache);
+    pending.penalties.extend(applied);
+
+    Ok(())
+}
+
+/// Initial state of an account, an entry of the `alloc` of a geth `genesis.json`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenesisAccount {
+    pub balance: U256,
+    pub nonce: U64,
+    pub code: Option<Bytes>,
+    pub storage: BTreeMap<B256, B256>,
+}
+
+/// Allocates the genesis accounts in the pending commit, replacing the state of any account
+/// that already exists.
+pub fn apply_genesis_alloc(
+    db: &mut PersistentDB,
+    pending: &mut PendingCommit,
+    alloc: BTreeMap<Address, GenesisAccount>,
+) -> Result<(), crate::db::Error> {
+    let mut state = revm::State::builder()
+        .with_bundle_update()
+        .with_cached_prestate(std::mem::take(&mut pending.cache))
+        .with_database(WrapDatabaseRef(&db))
+        .build();
+
+    let mut transitions = Vec::with_capacity(alloc.len());
+    for (address, genesis_account) in alloc {
+        let code = genesis_account
+            .code
+            .filter(|code| !code.is_empty())
+            .map(Bytecode::new_raw);
+        let info = AccountInfo {
+            balance: genesis_account.balance,
+            nonce: genesis_account.nonce.to(),
+            code_hash: code.as_ref().map_or(KECCAK_EMPTY, |code| code.hash_slow()),
+            code,
+        };
+        if let Some(code) = &info.code {
+            state.cache.contracts.insert(info.code_hash, code.clone());
+        }
+
+        let account = state.load_cache_account(address)?;
+        let storage = genesis_account
+            .storage
+            .into_iter()
+            .map(|(index, value)| {
+                let index = U256::from_be_bytes(index.0);
+                let original = account.storage_slot(index).unwrap_or_default();
+                (
+                    index,
+                    StorageSlot::new_changed(original, U256::from_be_bytes(value.0)),
+                )
+            })
+            .collect();
+        transitions.push((address, account.change(info, storage)));
+    }
+
+    pending.transitions.add_transitions(transitions);
+    pending.cache = std::mem::take(&mut state.cache);
+
+    Ok(())
+}
+
+const BPS: u64 = 10_000;
+
+/// Split of the fees collected in a commit. Shares are in basis points of the collected fees,
+/// the validator receives whatever is neither burned nor sent to the treasury.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct FeeDistribution {
+    pub burn_bps: u16,
+    pub treasury_bps: u16,
+    pub treasury: Address,
+}
+
+impl FeeDistribution {
+    pub fn new(burn_bps: u16, treasury_bps: u16, treasury: Address) -> Option<Self> {
+        (u64::from(burn_bps) + u64::from(treasury_bps) <= BPS).then_some(Self {
+            burn_bps,
+            treasury_bps,
+            treasury,
+        })
+    }
+}
+
+/// Fees of a commit and where they went.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct DistributedFees {
+    pub collected: U256,
+    pub burned: U256,
+    pub treasury: U256,
+    pub validator: U256,
+}
+
+/// Splits the fees collected in the pending commit according to its fee distribution and
+/// credits the treasury and `validator`. The burned share is not credited to anyone.
+///
+/// Does nothing if no fee distribution was set, fees are then paid to the coinbase of each
+/// transaction as usual.
+pub fn distribute_fees(
+    db: &mut PersistentDB,
+    pending: &mut PendingCommit,
+    validator: Address,
+) -> Result<(), crate::db::Error> {
+    let Some(distribution) = pending.fee_distribution else {
+        return Ok(());
+    };
+
+    let collected = pending.fees;
+    // split before multiplying so that no amount of fees can overflow
+    let share = |bps: u16| {
+        let (bps, total) = (U256::from(bps), U256::from(BPS));
+        collected / total * bps + collected % total * bps / total
+    };
+
+    let burned = share(distribution.burn_bps);
+    let treasury = share(distribution.treasury_bps);
+    let fees = DistributedFees {
+        collected,
+        burned,
+        treasury,
+        validator: collected - burned - treasury,
+    };
+
+    let mut credits = HashMap::<Address, U256>::new();
+    for (address, amount) in [
+        (distribution.treasury, fees.treasury),
+        (validator, fees.validator),
+    ] {
+        *credits.entry(address).or_default() += amount;
+    }
+
+    credit_balances(db, pending, credits)?;
+    pending.fees = U256::ZERO;
+
+    let total = pending
+        .distributed_fees
+        .get_or_insert_with(Default::default);
+    total.collected += fees.collected;
+    total.burned += fees.burned;
+    total.treasury += fees.treasury;
+    total.validator += fees.validator;
+
+    Ok(())
+}
+
+// Adds the full amounts to the balances, unlike `apply_rewards` which is limited to u128.
+fn credit_balances(
+    db: &mut PersistentDB,
+    pending: &mut PendingCommit,
+    credits: HashMap<Address, U256>,
+) -> Result<(), crate::db::Error> {
+    let credits = credits
+        .into_iter()
+        .filter(|(_, amount)| !amount.is_zero())
+        .collect::<Vec<_>>();
+
+    // check every balance first so that an overflow leaves the pending commit untouched
+    let state = PendingStateRef {
+        db: &*db,
+        cache: Some(&pending.cache),
+    };
+    for (address, amount) in &credits {
+        let balance = state
+            .basic_ref(*address)?
+            .map(|account| account.balance)
+            .unwrap_or_default();
+
+        if balance.checked_add(*amount).is_none() {
+            return Err(Error::RewardOverflow(*address));
+        }
+    }
+
+    let mut state = revm::State::builder()
+        .with_bundle_update()
+        .with_cached_prestate(std::mem::take(&mut pending.cache))
+        .with_database(WrapDatabaseRef(&db))
+        .build();
+
+    let mut transitions = Vec::with_capacity(credits.len());
+    let result = credits.into_iter().try_for_each(|(address, amount)| {
+        let account = state.load_cache_account(address)?;
+        let mut info = account.account_info().unwrap_or_default();
+        info.balance += amount;
+        transitions.push((address, account.change(info, Default::default())));
+        Ok::<_, Error>(())
+    });
+
+    pending.cache = std::mem::take(&mut state.cache);
+    result?;
+    pending.transitions.add_transitions(transitions);
+
+    Ok(())
+}
+
+/// Pending commit of height 0 allocating `alloc`. Votes can only be indexed from transactions,
+/// so the allocation must not set storage of the validator contract.
+pub fn genesis_commit(
+    db: &mut PersistentDB,
+    alloc: BTreeMap<Address, GenesisAccount>,
+) -> Result<PendingCommit, crate::db::Error> {
+    if let Some(genesis_info) = &db.genesis_info {
+        let validator_contract = genesis_info.validator_contract;
+        if alloc
+            .get(&validator_contract)
+            .is_some_and(|account| !account.storage.is_empty())
+        {
+            return Err(Error::GenesisValidatorStorage(validator_contract));
+        }
+    }
+
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+    apply_genesis_alloc(db, &mut pending, alloc)?;
+
+    Ok(pending)
+}
+
 pub fn commit_to_db(
     db: &mut PersistentDB,
     pending_commit: PendingCommit,
@@ -81,18 +475,13 @@ pub fn commit_to_db(
     let genesis_info = db.genesis_info.clone();
     let mut commit = build_commit(db, pending_commit, true)?;
 
-    match db.commit(&mut commit) {
-        Ok(_) => Ok(collect_dirty_accounts(commit, &genesis_info)),
-        Err(err) => match &err {
-            Error::DbFull => {
-                // try to resize the db and attempt another commit on success
-                db.resize().and_then(|_| {
-                    db.commit(&mut commit)
-                        .and_then(|_| Ok(collect_dirty_accounts(commit, &genesis_info)))
-                })
-            }
-            _ => Err(err),
-        },
+    loop {
+        match db.commit(&mut commit) {
+            Ok(_) => return Ok(collect_dirty_accounts(commit, &genesis_info)),
+            // keep growing the db until the commit fits or the max map size is reached
+            Err(Error::DbFull) => db.resize()?,
+            Err(err) => return Err(err),
+        }
     }
 }
 
@@ -196,3 +585,344 @@ fn test_apply_rewards() {
     assert!(pending.transitions.transitions.contains_key(&account1));
     assert!(!pending.transitions.transitions.contains_key(&account2));
 }
+
+#[test]
+fn test_distribute_fees() {
+    use revm::primitives::address;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    let treasury = address!("00000000000000000000000000000000000000aa");
+    let validator = address!("00000000000000000000000000000000000000bb");
+
+    assert_eq!(FeeDistribution::new(6_000, 5_000, treasury), None);
+
+    // without a distribution fees stay with the coinbase
+    let mut pending = PendingCommit::default();
+    distribute_fees(&mut db, &mut pending, validator).expect("distribute");
+    assert_eq!(pending.distributed_fees, None);
+    assert!(pending.cache.accounts.is_empty());
+
+    let mut pending = PendingCommit {
+        fees: U256::from(1_001),
+        fee_distribution: FeeDistribution::new(5_000, 2_000, treasury),
+        ..Default::default()
+    };
+    distribute_fees(&mut db, &mut pending, validator).expect("distribute");
+
+    assert_eq!(
+        pending.distributed_fees,
+        Some(DistributedFees {
+            collected: U256::from(1_001),
+            burned: U256::from(500),
+            treasury: U256::from(200),
+            // rounding goes to the validator
+            validator: U256::from(301),
+        })
+    );
+    assert_eq!(pending.fees, U256::ZERO);
+
+    let balance = |db: &PersistentDB, pending: &PendingCommit, address| {
+        crate::db::PendingStateRef {
+            db,
+            cache: Some(&pending.cache),
+        }
+        .basic_ref(address)
+        .expect("account")
+        .map(|account| account.balance)
+    };
+    assert_eq!(balance(&db, &pending, treasury), Some(U256::from(200)));
+    assert_eq!(balance(&db, &pending, validator), Some(U256::from(301)));
+
+    // amounts beyond u128 are credited in full
+    let collected = U256::from(u128::MAX) * U256::from(4);
+    let mut pending = PendingCommit {
+        fees: collected,
+        fee_distribution: FeeDistribution::new(0, 5_000, treasury),
+        ..Default::default()
+    };
+    distribute_fees(&mut db, &mut pending, validator).expect("distribute");
+    assert_eq!(
+        balance(&db, &pending, treasury),
+        Some(collected / U256::from(2))
+    );
+    assert_eq!(
+        balance(&db, &pending, validator),
+        Some(collected / U256::from(2))
+    );
+
+    // a balance overflow is an error and changes nothing
+    pending.fees = U256::MAX;
+    distribute_fees(&mut db, &mut pending, validator).expect("distribute");
+    pending.fees = U256::MAX;
+
+    let before = pending.clone();
+    assert!(matches!(
+        distribute_fees(&mut db, &mut pending, validator),
+        Err(Error::RewardOverflow(_))
+    ));
+    assert_eq!(pending.fees, U256::MAX);
+    assert_eq!(pending.distributed_fees, before.distributed_fees);
+    assert_eq!(pending.cache, before.cache);
+    assert_eq!(pending.transitions, before.transitions);
+}
+
+#[test]
+fn test_apply_reward_schedule() {
+    use revm::primitives::address;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let mut pending = PendingCommit::default();
+
+    let validator = address!("00000000000000000000000000000000000000bb");
+    let voter = address!("00000000000000000000000000000000000000cc");
+
+    let reward = |address, amount, kind: &str| Reward {
+        address,
+        amount,
+        kind: kind.into(),
+    };
+
+    let schedule = vec![
+        reward(validator, 100, "block"),
+        reward(voter, 40, "voter"),
+        reward(validator, 5, "missedRound"),
+    ];
+    apply_reward_schedule(&mut db, &mut pending, schedule.clone()).expect("rewards");
+    assert_eq!(pending.rewards, schedule);
+
+    fn balance(db: &PersistentDB, pending: &PendingCommit, address: Address) -> Option<U256> {
+        PendingStateRef {
+            db,
+            cache: Some(&pending.cache),
+        }
+        .basic_ref(address)
+        .expect("account")
+        .map(|account| account.balance)
+    }
+    assert_eq!(balance(&db, &pending, validator), Some(U256::from(105)));
+    assert_eq!(balance(&db, &pending, voter), Some(U256::from(40)));
+
+    // an overflowing entry rejects the whole schedule
+    let before = pending.clone();
+    assert!(matches!(
+        apply_reward_schedule(
+            &mut db,
+            &mut pending,
+            vec![
+                reward(voter, 1, "voter"),
+                reward(validator, u128::MAX, "block"),
+                reward(validator, 1, "devFund"),
+            ],
+        ),
+        Err(Error::RewardOverflow(address)) if address == validator
+    ));
+    assert_eq!(pending.rewards, before.rewards);
+    assert_eq!(balance(&db, &pending, voter), Some(U256::from(40)));
+}
+
+#[test]
+fn test_apply_penalties() {
+    use revm::primitives::address;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let mut pending = PendingCommit::default();
+
+    let validator = address!("00000000000000000000000000000000000000bb");
+    let other = address!("00000000000000000000000000000000000000cc");
+
+    apply_rewards(
+        &mut db,
+        &mut pending,
+        HashMap::from([(validator, 100), (other, 10)]),
+    )
+    .expect("rewards");
+
+    let penalty = |address, amount: u64, reason: &str| Penalty {
+        address,
+        amount: U256::from(amount),
+        reason: reason.into(),
+    };
+    fn balance(db: &PersistentDB, pending: &PendingCommit, address: Address) -> Option<U256> {
+        PendingStateRef {
+            db,
+            cache: Some(&pending.cache),
+        }
+        .basic_ref(address)
+        .expect("account")
+        .map(|account| account.balance)
+    }
+
+    // an insufficient balance rejects all penalties in strict mode
+    assert!(matches!(
+        apply_penalties(
+            &mut db,
+            &mut pending,
+            vec![
+                penalty(validator, 60, "doubleSign"),
+                penalty(validator, 60, "doubleSign"),
+            ],
+            PenaltyMode::Strict,
+        ),
+        Err(Error::InsufficientBalance { address, balance, amount })
+            if address == validator && balance == U256::from(40) && amount == U256::from(60)
+    ));
+    assert!(pending.penalties.is_empty());
+    assert_eq!(balance(&db, &pending, validator), Some(U256::from(100)));
+
+    apply_penalties(
+        &mut db,
+        &mut pending,
+        vec![
+            penalty(validator, 60, "doubleSign"),
+            penalty(validator, 60, "doubleSign"),
+            penalty(other, 5, "downtime"),
+        ],
+        PenaltyMode::Saturating,
+    )
+    .expect("penalties");
+
+    assert_eq!(balance(&db, &pending, validator), Some(U256::ZERO));
+    assert_eq!(balance(&db, &pending, other), Some(U256::from(5)));
+    assert_eq!(
+        pending
+            .penalties
+            .iter()
+            .map(|penalty| penalty.deducted)
+            .collect::<Vec<_>>(),
+        vec![U256::from(60), U256::from(40), U256::from(5)]
+    );
+
+    // penalties are part of the commit
+    let applied = pending.penalties.clone();
+    let commit = build_commit(&mut db, pending.clone(), false).expect("commit");
+    assert_eq!(
+        commit
+            .change_set
+            .accounts
+            .iter()
+            .find(|(address, _)| *address == other)
+            .and_then(|(_, account)| account.as_ref())
+            .map(|account| account.balance),
+        Some(U256::from(5))
+    );
+    assert_eq!(commit.penalties, applied);
+
+    // and can be read back after reopening the database
+    commit_to_db(&mut db, pending).expect("commit");
+    db.close();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    assert_eq!(
+        db.get_committed_penalties(0).expect("penalties"),
+        Some(applied)
+    );
+    assert_eq!(db.get_committed_penalties(1).expect("penalties"), None);
+}
+
+#[test]
+fn test_genesis_commit() {
+    use revm::primitives::address;
+
+    use crate::state_hash;
+
+    let alloc: BTreeMap<Address, GenesisAccount> = serde_json::from_str(
+        r#"{
+            "0x00000000000000000000000000000000000000aa": {
+                "balance": "0x3635c9adc5dea00000",
+                "nonce": "0x2"
+            },
+            "0x00000000000000000000000000000000000000bb": {
+                "balance": "0x0",
+                "code": "0x600160005500",
+                "storage": {
+                    "0x0000000000000000000000000000000000000000000000000000000000000001": "0x000000000000000000000000000000000000000000000000000000000000002a"
+                }
+            }
+        }"#,
+    )
+    .expect("alloc");
+
+    let account = address!("00000000000000000000000000000000000000aa");
+    let contract = address!("00000000000000000000000000000000000000bb");
+
+    let open = || {
+        let path = tempfile::Builder::new()
+            .prefix("evm.mdb")
+            .tempdir()
+            .unwrap();
+        let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+        (path, db)
+    };
+
+    let (_path, mut db) = open();
+    let pending = genesis_commit(&mut db, alloc.clone()).expect("genesis");
+    let state_hash =
+        state_hash::calculate(&mut db, pending.clone(), B256::ZERO).expect("state hash");
+    commit_to_db(&mut db, pending).expect("commit");
+
+    let info = db.basic_ref(account).expect("account").expect("exists");
+    assert_eq!(
+        info.balance,
+        U256::from(1_000) * U256::from(10).pow(U256::from(18))
+    );
+    assert_eq!(info.nonce, 2);
+
+    let info = db.basic_ref(contract).expect("account").expect("exists");
+    let code = db.code_by_hash_ref(info.code_hash).expect("code");
+    assert_eq!(
+        code.original_bytes(),
+        Bytes::from_static(&[0x60, 0x01, 0x60, 0x00, 0x55, 0x00])
+    );
+    assert_eq!(
+        db.storage_ref(contract, U256::from(1)).expect("storage"),
+        U256::from(42)
+    );
+
+    // accounts outside of the allocation are empty
+    let other = db
+        .basic_ref(Address::ZERO)
+        .expect("account")
+        .expect("default");
+    assert!(other.balance.is_zero());
+
+    // the committed genesis keeps the state hash of its pending commit
+    assert_eq!(
+        state_hash::calculate(&mut db, PendingCommit::new(CommitKey(0, 0)), B256::ZERO)
+            .expect("state hash"),
+        state_hash
+    );
+
+    // and the same allocation gives the same state hash
+    let (_path, mut other_db) = open();
+    let pending = genesis_commit(&mut other_db, alloc.clone()).expect("genesis");
+    assert_eq!(
+        state_hash::calculate(&mut other_db, pending, B256::ZERO).expect("state hash"),
+        state_hash
+    );
+
+    // storage of the validator contract would bypass the voter index
+    other_db.set_genesis_info(GenesisInfo {
+        deployer_account: account,
+        validator_contract: contract,
+    });
+    assert!(matches!(
+        genesis_commit(&mut other_db, alloc),
+        Err(Error::GenesisValidatorStorage(address)) if address == contract
+    ));
+}
@@ -0,0 +1,705 @@
+use alloy_sol_types::{sol, SolCall};
+use revm::{
+    primitives::{
+        address, keccak256, Address, BlockEnv, EVMError, ExecutionResult, HaltReason, SpecId,
+        TransactTo, TxEnv, B256, U256,
+    },
+    DatabaseRef, Evm,
+};
+
+use crate::{
+    db::{Error, PendingCommit, PersistentDB, ValidatorSetEntry},
+    execution,
+    precompiles::PrecompileRegistry,
+};
+
+sol!(
+    #[sol(all_derives)]
+    "../../../contracts/src/consensus/Consensus.sol"
+);
+
+/// Reserved sender of system calls. No key controls it, so it never sends user transactions.
+pub const SYSTEM_ADDRESS: Address = address!("fffffffffffffffffffffffffffffffffffffffe");
+
+/// Gas available to a single system call. Calls are free, the limit only bounds how long a
+/// misbehaving system contract can run.
+pub const SYSTEM_CALL_GAS_LIMIT: u64 = 30_000_000;
+
+/// Why a system call did not produce its return value.
+#[derive(thiserror::Error, Debug)]
+pub enum SystemCallError {
+    #[error("{call} failed: {error}")]
+    Execution {
+        call: &'static str,
+        error: EVMError<Error>,
+    },
+    #[error("{call} reverted: {reason}")]
+    Reverted { call: &'static str, reason: String },
+    #[error("{call} ran out of gas")]
+    OutOfGas { call: &'static str },
+    #[error("{call} halted: {reason:?}")]
+    Halted {
+        call: &'static str,
+        reason: HaltReason,
+    },
+    #[error("{call} returned invalid data: {error}")]
+    Decode {
+        call: &'static str,
+        error: alloy_sol_types::Error,
+    },
+}
+
+/// Block a system call is executed in, its height is the one of the pending commit.
+#[derive(Clone, Debug)]
+pub struct SystemCallBlock {
+    pub timestamp: U256,
+    /// Validator producing the block
+    pub coinbase: Address,
+    pub spec_id: SpecId,
+}
+
+/// Decoded return value of a system call together with its execution result.
+#[derive(Clone, Debug)]
+pub struct SystemCallOutput<T> {
+    /// Synthetic hash the receipt of the call is stored under, see [`system_tx_hash`]
+    pub tx_hash: B256,
+    pub returns: T,
+    pub result: ExecutionResult,
+}
+
+/// Calls from the protocol into system contracts like [`Consensus`], sent from
+/// [`SYSTEM_ADDRESS`]. Calls are free, do not count towards the gas limit of the block and leave
+/// the system address untouched, see [`execution::system_call_handler`].
+pub struct SystemCaller<'a> {
+    db: &'a PersistentDB,
+    precompiles: &'a PrecompileRegistry,
+}
+
+impl<'a> SystemCaller<'a> {
+    pub fn new(db: &'a PersistentDB, precompiles: &'a PrecompileRegistry) -> Self {
+        Self { db, precompiles }
+    }
+
+    /// Executes `call` on `contract` as part of the pending commit. The call is not a
+    /// transaction of the commit, but its result is kept with it under a synthetic hash, even
+    /// if the call reverted.
+    pub fn call<C: SolCall>(
+        &self,
+        pending: &mut PendingCommit,
+        block: &SystemCallBlock,
+        contract: Address,
+        call: C,
+    ) -> Result<SystemCallOutput<C::Return>, SystemCallError> {
+        let block_env = BlockEnv {
+            number: U256::from(pending.key.0),
+            coinbase: block.coinbase,
+            timestamp: block.timestamp,
+            gas_limit: U256::from(SYSTEM_CALL_GAS_LIMIT),
+            difficulty: U256::ZERO,
+            prevrandao: Some(pending.randomness),
+            ..Default::default()
+        };
+
+        let result = execution::execute_system_call(
+            self.db,
+            pending,
+            &block_env,
+            block.spec_id,
+            self.precompiles,
+            self.tx_env(contract, &call),
+        )
+        .map_err(|error| SystemCallError::Execution {
+            call: C::SIGNATURE,
+            error,
+        })?;
+
+        let tx_hash = system_tx_hash(pending.key.0, pending.system_results.len() as u64);
+        pending.system_results.push((tx_hash, result.clone()));
+
+        Ok(SystemCallOutput {
+            tx_hash,
+            returns: decode::<C>(&result)?,
+            result,
+        })
+    }
+
+    /// Executes `call` on `contract` on top of `state` without changing it.
+    pub fn view<C: SolCall, DB: DatabaseRef<Error = Error>>(
+        &self,
+        state: DB,
+        height: u64,
+        spec_id: SpecId,
+        contract: Address,
+        call: C,
+    ) -> Result<C::Return, SystemCallError> {
+        let result = Evm::builder()
+            .with_ref_db(state)
+            .with_spec_id(spec_id)
+            .modify_block_env(|block_env| {
+                block_env.number = U256::from(height);
+                block_env.gas_limit = U256::from(SYSTEM_CALL_GAS_LIMIT);
+            })
+            .with_tx_env(self.tx_env(contract, &call))
+            .append_handler_register(execution::system_call_handler)
+            .append_handler_register_box(self.precompiles.handler_register(height))
+            .build()
+            .transact()
+            .map_err(|error| SystemCallError::Execution {
+                call: C::SIGNATURE,
+                error,
+            })?
+            .result;
+
+        decode::<C>(&result)
+    }
+
+    /// Vote of `voter` in the consensus contract at `contract`, `None` if it does not vote.
+    pub fn vote_of<DB: DatabaseRef<Error = Error>>(
+        &self,
+        state: DB,
+        height: u64,
+        spec_id: SpecId,
+        contract: Address,
+        voter: Address,
+    ) -> Result<Option<Vote>, SystemCallError> {
+        let vote = self
+            .view(
+                state,
+                height,
+                spec_id,
+                contract,
+                Consensus::getVoteCall { addr: voter },
+            )?
+            ._0;
+
+        Ok((!vote.validator.is_zero()).then_some(vote))
+    }
+
+    fn tx_env<C: SolCall>(&self, contract: Address, call: &C) -> TxEnv {
+        TxEnv {
+            caller: SYSTEM_ADDRESS,
+            transact_to: TransactTo::Call(contract),
+            data: call.abi_encode().into(),
+            gas_limit: SYSTEM_CALL_GAS_LIMIT,
+            gas_price: U256::ZERO,
+            value: U256::ZERO,
+            nonce: None,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<Validator> for ValidatorSetEntry {
+    fn from(validator: Validator) -> Self {
+        Self {
+            address: validator.addr,
+            bls_public_key: validator.data.bls12_381_public_key,
+            vote_balance: validator.data.voteBalance,
+            voters_count: validator.data.votersCount,
+        }
+    }
+}
+
+/// Hash identifying the `index`-th system call of the commit at `height`. It is derived from the
+/// system address, so it cannot collide with the hash of a signed transaction.
+pub fn system_tx_hash(height: u64, index: u64) -> B256 {
+    keccak256(
+        [
+            SYSTEM_ADDRESS.as_slice(),
+            &height.to_be_bytes(),
+            &index.to_be_bytes(),
+        ]
+        .concat(),
+    )
+}
+
+fn decode<C: SolCall>(result: &ExecutionResult) -> Result<C::Return, SystemCallError> {
+    match result {
+        ExecutionResult::Success { output, .. } => C::abi_decode_returns(output.data(), true)
+            .map_err(|error| SystemCallError::Decode {
+                call: C::SIGNATURE,
+                error,
+            }),
+        ExecutionResult::Revert { output, .. } => Err(SystemCallError::Reverted {
+            call: C::SIGNATURE,
+            reason: alloy_sol_types::decode_revert_reason(output)
+                .unwrap_or_else(|| output.to_string()),
+        }),
+        ExecutionResult::Halt {
+            reason: HaltReason::OutOfGas(_),
+            ..
+        } => Err(SystemCallError::OutOfGas { call: C::SIGNATURE }),
+        ExecutionResult::Halt { reason, .. } => Err(SystemCallError::Halted {
+            call: C::SIGNATURE,
+            reason: *reason,
+        }),
+    }
+}
+
+// Executes a transaction recorded in the pending commit like the ones of a block.
+#[cfg(test)]
+pub(crate) fn transact(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    caller: Address,
+    transact_to: TransactTo,
+    data: revm::primitives::Bytes,
+) -> ExecutionResult {
+    execution::execute_sequential(
+        db,
+        pending,
+        &BlockEnv::default(),
+        SpecId::SHANGHAI,
+        &PrecompileRegistry::default(),
+        None,
+        vec![execution::Transaction {
+            tx_hash: Some(keccak256(
+                [caller.as_slice(), &pending.results.len().to_be_bytes()].concat(),
+            )),
+            env: TxEnv {
+                caller,
+                transact_to,
+                data,
+                gas_limit: 10_000_000,
+                gas_price: U256::ZERO,
+                ..Default::default()
+            },
+        }],
+    )
+    .outcomes
+    .pop()
+    .expect("outcome")
+    .expect("executed")
+}
+
+// Deploys the consensus contract from its compiled artifact. The artifact predates
+// `onlySystem` and still guards protocol functions with the owner, so tests deploy it from
+// `SYSTEM_ADDRESS` to let system calls pass that check.
+#[cfg(test)]
+pub(crate) fn deploy_consensus(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    deployer: Address,
+) -> Address {
+    let artifact: serde_json::Value = serde_json::from_str(include_str!(
+        "../../../evm-contracts/source/abis/Consensus.json"
+    ))
+    .expect("artifact");
+    let bytecode = artifact["bytecode"]["object"]
+        .as_str()
+        .expect("bytecode")
+        .parse()
+        .expect("hex");
+
+    let nonce = db
+        .basic_ref(deployer)
+        .expect("account")
+        .map_or(0, |account| account.nonce);
+    assert!(transact(db, pending, deployer, TransactTo::Create, bytecode).is_success());
+
+    deployer.create(nonce)
+}
+
+#[cfg(test)]
+pub(crate) fn register_validator(
+    db: &PersistentDB,
+    pending: &mut PendingCommit,
+    consensus: Address,
+    validator: Address,
+) {
+    let register = Consensus::registerValidatorCall {
+        bls12_381_public_key: vec![validator.0[19]; 48].into(),
+    };
+
+    assert!(transact(
+        db,
+        pending,
+        validator,
+        TransactTo::Call(consensus),
+        register.abi_encode().into()
+    )
+    .is_success());
+}
+
+#[test]
+fn test_system_call() {
+    use crate::db::{CommitKey, PendingStateRef};
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let precompiles = PrecompileRegistry::default();
+    let mut pending = PendingCommit::new(CommitKey(1, 0));
+
+    let validator = address!("00000000000000000000000000000000000000bb");
+    let unregistered = address!("00000000000000000000000000000000000000cc");
+
+    let block = SystemCallBlock {
+        timestamp: U256::from(1),
+        coinbase: validator,
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    let consensus = deploy_consensus(&db, &mut pending, SYSTEM_ADDRESS);
+    register_validator(&db, &mut pending, consensus, validator);
+
+    let system_account = pending.cache.accounts[&SYSTEM_ADDRESS].account_info();
+    let transactions = pending.results.len();
+
+    let system_caller = SystemCaller::new(&db, &precompiles);
+    let output = system_caller
+        .call(
+            &mut pending,
+            &block,
+            consensus,
+            Consensus::calculateTopValidatorsCall { n: 1 },
+        )
+        .expect("calculateTopValidators");
+    assert!(output.result.is_success());
+
+    // system calls are neither charged nor recorded as transactions
+    assert_eq!(
+        pending.cache.accounts[&SYSTEM_ADDRESS].account_info(),
+        system_account
+    );
+    assert_eq!(pending.results.len(), transactions);
+
+    let top_validators = system_caller
+        .view(
+            PendingStateRef {
+                db: &db,
+                cache: Some(&pending.cache),
+            },
+            1,
+            block.spec_id,
+            consensus,
+            Consensus::getTopValidatorsCall {},
+        )
+        .expect("getTopValidators")
+        ._0;
+    assert_eq!(top_validators.len(), 1);
+    assert_eq!(top_validators[0].addr, validator);
+    assert_eq!(top_validators[0].data.bls12_381_public_key, vec![0xbb; 48]);
+
+    // revert reasons are decoded
+    let result = system_caller.call(
+        &mut pending,
+        &block,
+        consensus,
+        Consensus::getValidatorCall {
+            _addr: unregistered,
+        },
+    );
+    assert!(matches!(
+        result,
+        Err(SystemCallError::Reverted { reason, .. }) if reason.contains("ValidatorData doesn't exists")
+    ));
+    assert_eq!(
+        pending.cache.accounts[&SYSTEM_ADDRESS].account_info(),
+        system_account
+    );
+}
+
+#[test]
+fn test_system_call_without_account() {
+    use crate::db::CommitKey;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let precompiles = PrecompileRegistry::default();
+
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+    let consensus = deploy_consensus(
+        &db,
+        &mut pending,
+        address!("0000000000000000000000000000000000000d00"),
+    );
+    crate::state_commit::commit_to_db(&mut db, pending).expect("commit");
+
+    // the system address needs no account, nor do system calls create one
+    let mut pending = PendingCommit::new(CommitKey(1, 0));
+    let block = SystemCallBlock {
+        timestamp: U256::from(1),
+        coinbase: Address::ZERO,
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    let output = SystemCaller::new(&db, &precompiles)
+        .call(
+            &mut pending,
+            &block,
+            consensus,
+            Consensus::registeredValidatorsCountCall {},
+        )
+        .expect("registeredValidatorsCount");
+    assert_eq!(output.returns._0, U256::ZERO);
+
+    assert!(pending.results.is_empty());
+
+    let updates = crate::state_commit::commit_to_db(&mut db, pending).expect("commit");
+    assert!(updates
+        .iter()
+        .all(|update| update.address != SYSTEM_ADDRESS));
+    assert_eq!(
+        db.basic_ref(SYSTEM_ADDRESS).expect("account"),
+        Some(Default::default())
+    );
+}
+
+#[test]
+fn test_system_call_receipts() {
+    use crate::db::CommitKey;
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let precompiles = PrecompileRegistry::default();
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+
+    let validator = address!("00000000000000000000000000000000000000bb");
+    let block = SystemCallBlock {
+        timestamp: U256::from(1),
+        coinbase: validator,
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    let consensus = deploy_consensus(&db, &mut pending, SYSTEM_ADDRESS);
+    register_validator(&db, &mut pending, consensus, validator);
+    let gas_used = pending.gas_used;
+    let (register_hash, register_position) = pending
+        .positions
+        .iter()
+        .max_by_key(|(_, position)| position.index)
+        .map(|(tx_hash, position)| (*tx_hash, *position))
+        .expect("register transaction");
+
+    let system_caller = SystemCaller::new(&db, &precompiles);
+    let output = system_caller
+        .call(
+            &mut pending,
+            &block,
+            consensus,
+            Consensus::calculateTopValidatorsCall { n: 1 },
+        )
+        .expect("calculateTopValidators");
+    assert_eq!(output.tx_hash, system_tx_hash(0, 0));
+
+    // reverted calls are kept as well
+    assert!(system_caller
+        .call(
+            &mut pending,
+            &block,
+            consensus,
+            Consensus::getValidatorCall {
+                _addr: Address::ZERO,
+            },
+        )
+        .is_err());
+
+    let hashes = pending
+        .system_results
+        .iter()
+        .map(|(tx_hash, _)| *tx_hash)
+        .collect::<Vec<_>>();
+    assert_eq!(hashes, vec![system_tx_hash(0, 0), system_tx_hash(0, 1)]);
+    let reverted_gas = pending.system_results[1].1.gas_used();
+
+    // system calls use no gas of the commit
+    assert_eq!(pending.gas_used, gas_used);
+
+    crate::state_commit::commit_to_db(&mut db, pending).expect("commit");
+    assert_eq!(db.get_committed_gas_used(0).expect("gas"), Some(gas_used));
+
+    // transactions keep their positions, system calls follow them
+    let (_, receipt) = db.get_committed_receipt(0, register_hash).expect("receipt");
+    let receipt = receipt.expect("transaction receipt");
+    assert!(receipt.success);
+    assert_eq!(receipt.tx_index, register_position.index);
+    assert_eq!(receipt.cumulative_gas_used, gas_used);
+
+    let (_, receipt) = db
+        .get_committed_receipt(0, system_tx_hash(0, 0))
+        .expect("receipt");
+    let receipt = receipt.expect("system call receipt");
+    assert!(receipt.success);
+    assert_eq!(receipt.tx_index, 2);
+    assert_eq!(receipt.gas_used, output.result.gas_used());
+    assert_eq!(
+        receipt.cumulative_gas_used,
+        gas_used + output.result.gas_used()
+    );
+
+    let (_, receipt) = db
+        .get_committed_receipt(0, system_tx_hash(0, 1))
+        .expect("receipt");
+    let receipt = receipt.expect("system call receipt");
+    assert!(!receipt.success);
+    assert_eq!(receipt.tx_index, 3);
+    assert_eq!(
+        receipt.cumulative_gas_used,
+        gas_used + output.result.gas_used() + reverted_gas
+    );
+}
+
+#[test]
+fn test_system_call_out_of_gas() {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        db::CommitKey,
+        state_commit::{apply_genesis_alloc, GenesisAccount},
+    };
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let precompiles = PrecompileRegistry::default();
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+
+    // loops forever: JUMPDEST PUSH1 0 JUMP
+    let contract = address!("0000000000000000000000000000000000000c00");
+    let alloc = BTreeMap::from([(
+        contract,
+        GenesisAccount {
+            code: Some("0x5b600056".parse().expect("hex")),
+            ..Default::default()
+        },
+    )]);
+    apply_genesis_alloc(&mut db, &mut pending, alloc).expect("alloc");
+
+    let block = SystemCallBlock {
+        timestamp: U256::from(1),
+        coinbase: Address::ZERO,
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    let result = SystemCaller::new(&db, &precompiles).call(
+        &mut pending,
+        &block,
+        contract,
+        Consensus::calculateTopValidatorsCall { n: 1 },
+    );
+    assert!(matches!(result, Err(SystemCallError::OutOfGas { .. })));
+
+    // the call is kept with the commit and used up the whole system call limit
+    let (_, result) = pending.system_results.last().expect("system result");
+    assert_eq!(result.gas_used(), SYSTEM_CALL_GAS_LIMIT);
+}
+
+#[test]
+fn test_vote_of() {
+    use std::collections::BTreeMap;
+
+    use crate::{
+        db::{CommitKey, PendingStateRef},
+        state_commit::{apply_genesis_alloc, GenesisAccount},
+    };
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    let precompiles = PrecompileRegistry::default();
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+
+    let consensus = address!("0000000000000000000000000000000000000c00");
+    let validator = address!("00000000000000000000000000000000000000bb");
+    let voter = address!("00000000000000000000000000000000000000cc");
+    let non_voter = address!("00000000000000000000000000000000000000dd");
+
+    // The compiled consensus artifact predates getVote, so a stand-in answers it instead: it
+    // reverts on any other selector and returns the (validator, balance) pair stored at the
+    // slots `voter` and `voter + 1`.
+    let code = format!(
+        "0x60003560e01c63{}14601357600080fd5b60043580546000526001015460205260406000f3",
+        revm::primitives::hex::encode(Consensus::getVoteCall::SELECTOR)
+    )
+    .parse()
+    .expect("hex");
+    let voter_slot = U256::from_be_bytes(voter.into_word().0);
+    let alloc = BTreeMap::from([(
+        consensus,
+        GenesisAccount {
+            code: Some(code),
+            storage: BTreeMap::from([
+                (voter_slot.into(), validator.into_word()),
+                (
+                    (voter_slot + U256::from(1)).into(),
+                    U256::from(1_000).into(),
+                ),
+            ]),
+            ..Default::default()
+        },
+    )]);
+    apply_genesis_alloc(&mut db, &mut pending, alloc).expect("alloc");
+
+    let vote = |voter| {
+        SystemCaller::new(&db, &precompiles)
+            .vote_of(
+                PendingStateRef {
+                    db: &db,
+                    cache: Some(&pending.cache),
+                },
+                0,
+                SpecId::SHANGHAI,
+                consensus,
+                voter,
+            )
+            .expect("vote")
+    };
+
+    assert_eq!(
+        vote(voter),
+        Some(Vote {
+            validator,
+            balance: U256::from(1_000)
+        })
+    );
+    assert_eq!(vote(non_voter), None);
+}
+
+// The compiled artifact has to be rebuilt with `forge build` whenever Consensus.sol changes,
+// otherwise the node deploys a contract that lacks the functions it calls.
+#[test]
+#[ignore = "Consensus.json predates Consensus.sol and needs to be rebuilt with forge"]
+fn test_consensus_artifact_matches_source() {
+    let artifact: serde_json::Value = serde_json::from_str(include_str!(
+        "../../../evm-contracts/source/abis/Consensus.json"
+    ))
+    .expect("artifact");
+
+    let selectors = artifact["methodIdentifiers"]
+        .as_object()
+        .expect("method identifiers")
+        .values()
+        .map(|selector| selector.as_str().expect("selector").to_owned())
+        .collect::<std::collections::HashSet<_>>();
+    let missing = Consensus::ConsensusCalls::SELECTORS
+        .iter()
+        .map(revm::primitives::hex::encode)
+        .filter(|selector| !selectors.contains(selector))
+        .collect::<Vec<_>>();
+    assert!(missing.is_empty(), "functions missing: {missing:?}");
+
+    // onlySystem compares the caller to the system address
+    let bytecode = artifact["deployedBytecode"]["object"]
+        .as_str()
+        .expect("bytecode")
+        .to_lowercase();
+    assert!(bytecode.contains(&revm::primitives::hex::encode(SYSTEM_ADDRESS)));
+}
@@ -0,0 +1,266 @@
+use revm::{
+    primitives::{
+        AccountInfo, Address, BlockEnv, Bytecode, EVMError, InvalidTransaction, SpecId, TxEnv,
+        B256, U256,
+    },
+    DatabaseRef, Evm,
+};
+
+use crate::db::{Error, PersistentDB};
+
+/// Outcome of validating a transaction without executing it.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TxValidation {
+    /// Reason the transaction would be rejected, `None` if it is valid.
+    pub error: Option<InvalidTransaction>,
+    /// Gas charged before any bytecode runs.
+    pub intrinsic_gas: u64,
+    /// Nonce the transaction is expected to have.
+    pub nonce: u64,
+    /// Committed balance of the sender.
+    pub balance: U256,
+}
+
+impl TxValidation {
+    pub fn is_valid(&self) -> bool {
+        self.error.is_none()
+    }
+}
+
+/// Runs revm's validation of the environment, intrinsic gas and sender account against the
+/// committed state, without touching any pending commit.
+///
+/// `pending_nonce` is the next nonce of the sender including transactions that are still in
+/// the pool, it is only used if it is ahead of the committed nonce.
+pub fn validate_transaction(
+    db: &PersistentDB,
+    block_env: BlockEnv,
+    spec_id: SpecId,
+    tx_env: TxEnv,
+    pending_nonce: Option<u64>,
+) -> Result<TxValidation, EVMError<Error>> {
+    let caller = tx_env.caller;
+    let account = db
+        .basic_ref(caller)
+        .map_err(EVMError::Database)?
+        .unwrap_or_default();
+    let nonce = account.nonce.max(pending_nonce.unwrap_or_default());
+
+    let intrinsic_gas = revm::interpreter::gas::validate_initial_tx_gas(
+        spec_id,
+        &tx_env.data,
+        tx_env.transact_to.is_create(),
+        &tx_env.access_list,
+        tx_env
+            .authorization_list
+            .as_ref()
+            .map(|list| list.len() as u64)
+            .unwrap_or_default(),
+    );
+
+    let mut validation = TxValidation {
+        error: None,
+        intrinsic_gas,
+        nonce,
+        balance: account.balance,
+    };
+
+    let mut evm = Evm::builder()
+        .with_ref_db(PendingNonce { db, caller, nonce })
+        .with_spec_id(spec_id)
+        .with_block_env(block_env)
+        .with_tx_env(tx_env)
+        .build();
+
+    let handler = evm.handler.validation();
+    let result = handler
+        .env(&evm.context.evm.env)
+        .and_then(|_| handler.initial_tx_gas(&evm.context.evm.env))
+        .and_then(|_| handler.tx_against_state(&mut evm.context));
+
+    match result {
+        Ok(_) => Ok(validation),
+        Err(EVMError::Transaction(err)) => {
+            validation.error = Some(err);
+            Ok(validation)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+// Committed state with the nonce of the sender advanced to the pending nonce.
+struct PendingNonce<'a> {
+    db: &'a PersistentDB,
+    caller: Address,
+    nonce: u64,
+}
+
+impl DatabaseRef for PendingNonce<'_> {
+    type Error = Error;
+
+    fn basic_ref(&self, address: Address) -> Result<Option<AccountInfo>, Self::Error> {
+        let mut account = self.db.basic_ref(address)?;
+        if address == self.caller {
+            if let Some(account) = account.as_mut() {
+                account.nonce = self.nonce;
+            }
+        }
+
+        Ok(account)
+    }
+
+    fn code_by_hash_ref(&self, code_hash: B256) -> Result<Bytecode, Self::Error> {
+        self.db.code_by_hash_ref(code_hash)
+    }
+
+    fn storage_ref(&self, address: Address, index: U256) -> Result<U256, Self::Error> {
+        self.db.storage_ref(address, index)
+    }
+
+    fn block_hash_ref(&self, number: u64) -> Result<B256, Self::Error> {
+        self.db.block_hash_ref(number)
+    }
+}
+
+#[test]
+fn test_validate_transaction() {
+    use revm::{
+        db::{AccountStatus, TransitionAccount},
+        primitives::{address, Bytes, HashMap, TransactTo},
+        TransitionState,
+    };
+
+    use crate::db::{CommitKey, PendingCommit};
+
+    let caller = address!("bd6f65c58a46427af4b257cbe231d0ed69ed5508");
+    let recipient = address!("0000000000000000000000000000000000000001");
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+
+    let mut transitions = HashMap::default();
+    transitions.insert(
+        caller,
+        TransitionAccount {
+            status: AccountStatus::InMemoryChange,
+            info: Some(AccountInfo {
+                balance: U256::from(1_000_000),
+                nonce: 3,
+                ..Default::default()
+            }),
+            previous_status: AccountStatus::Loaded,
+            previous_info: None,
+            storage: Default::default(),
+            storage_was_destroyed: false,
+        },
+    );
+    crate::state_commit::commit_to_db(
+        &mut db,
+        PendingCommit {
+            key: CommitKey(0, 0),
+            transitions: TransitionState { transitions },
+            ..Default::default()
+        },
+    )
+    .expect("commit");
+
+    let transfer = TxEnv {
+        caller,
+        transact_to: TransactTo::Call(recipient),
+        value: U256::from(100),
+        nonce: Some(3),
+        gas_limit: 21_000,
+        gas_price: U256::from(10),
+        ..Default::default()
+    };
+
+    let validate = |tx_env: TxEnv, pending_nonce| {
+        validate_transaction(
+            &db,
+            Default::default(),
+            SpecId::SHANGHAI,
+            tx_env,
+            pending_nonce,
+        )
+        .expect("validate")
+    };
+
+    let valid = validate(transfer.clone(), None);
+    assert_eq!(
+        valid,
+        TxValidation {
+            error: None,
+            intrinsic_gas: 21_000,
+            nonce: 3,
+            balance: U256::from(1_000_000),
+        }
+    );
+
+    // pending nonce from the pool is ahead of the committed one
+    let stale = validate(transfer.clone(), Some(5));
+    assert_eq!(
+        stale.error,
+        Some(InvalidTransaction::NonceTooLow { tx: 3, state: 5 })
+    );
+    assert!(validate(
+        TxEnv {
+            nonce: Some(5),
+            ..transfer.clone()
+        },
+        Some(5)
+    )
+    .is_valid());
+
+    // a pending nonce behind the committed nonce is ignored
+    assert!(validate(transfer.clone(), Some(1)).is_valid());
+
+    // gas_limit * gas_price + value exceeds the balance
+    assert!(matches!(
+        validate(
+            TxEnv {
+                gas_price: U256::from(100),
+                ..transfer.clone()
+            },
+            None
+        )
+        .error,
+        Some(InvalidTransaction::LackOfFundForMaxFee { .. })
+    ));
+
+    // not enough gas for the intrinsic cost
+    let intrinsic = validate(
+        TxEnv {
+            gas_limit: 20_000,
+            data: Bytes::from(vec![1; 10]),
+            ..transfer.clone()
+        },
+        None,
+    );
+    assert_eq!(
+        intrinsic.error,
+        Some(InvalidTransaction::CallGasCostMoreThanGasLimit)
+    );
+    assert_eq!(intrinsic.intrinsic_gas, 21_000 + 10 * 16);
+
+    // init code above the shanghai limit
+    let create = validate(
+        TxEnv {
+            transact_to: TransactTo::Create,
+            value: U256::ZERO,
+            gas_limit: 50_000,
+            data: Bytes::from(vec![0; 2 * revm::primitives::MAX_INITCODE_SIZE]),
+            ..transfer.clone()
+        },
+        None,
+    );
+    assert_eq!(
+        create.error,
+        Some(InvalidTransaction::CreateInitCodeSizeLimit)
+    );
+    // zero bytes plus the init code word cost
+    let len = 2 * revm::primitives::MAX_INITCODE_SIZE as u64;
+    assert_eq!(create.intrinsic_gas, 53_000 + len * 4 + len / 32 * 2);
+}
@@ -0,0 +1,276 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use alloy_sol_types::SolEvent;
+use revm::{
+    primitives::{Address, ExecutionResult, B256},
+    DatabaseRef,
+};
+
+use crate::{
+    db::{AddressWrapper, Error, InnerStorage, PendingCommit, PersistentDB},
+    events::{Unvoted, Voted},
+    receipt::TxPosition,
+};
+
+// Set once the voter index covers every vote, i.e. it was maintained since the first commit
+pub(crate) const VOTER_INDEX_KEY: &str = "voter_index";
+
+/// Maximum number of voters passed to a single `updateVoters` call.
+pub const UPDATE_VOTERS_CHUNK_SIZE: usize = 500;
+
+/// Votes cast (`Some(validator)`) or withdrawn (`None`) by the transactions of a commit, in
+/// the order they were executed.
+pub(crate) fn vote_changes(
+    validator_contract: Address,
+    results: &BTreeMap<B256, ExecutionResult>,
+    positions: &BTreeMap<B256, TxPosition>,
+) -> Vec<(Address, Option<Address>)> {
+    let mut results = results.iter().collect::<Vec<_>>();
+    results.sort_by_key(|(tx_hash, _)| positions.get(*tx_hash).map(|position| position.index));
+
+    let mut changes = Vec::new();
+    for (_, result) in results {
+        let ExecutionResult::Success { logs, .. } = result else {
+            continue;
+        };
+
+        for log in logs.iter().filter(|log| log.address == validator_contract) {
+            if let Ok(event) = Voted::decode_log(log, true) {
+                changes.push((event.voter, Some(event.validator)));
+            } else if let Ok(event) = Unvoted::decode_log(log, true) {
+                changes.push((event.voter, None));
+            }
+        }
+    }
+
+    changes
+}
+
+pub(crate) fn index(
+    inner: &InnerStorage,
+    rwtxn: &mut heed::RwTxn,
+    changes: Vec<(Address, Option<Address>)>,
+) -> Result<(), Error> {
+    for (voter, validator) in changes {
+        match validator {
+            Some(validator) => inner
+                .voters
+                .put(rwtxn, &AddressWrapper(voter), &validator)?,
+            None => {
+                inner.voters.delete(rwtxn, &AddressWrapper(voter))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Accounts whose vote balance in the consensus contract may be out of date after the pending
+/// commit: indexed voters whose balance changed and accounts that voted in the pending commit.
+///
+/// Falls back to every account touched by the pending commit if the voter index is incomplete,
+/// e.g. for databases created before it existed.
+pub fn changed_voters(
+    db: &PersistentDB,
+    pending: &PendingCommit,
+    validator_contract: Address,
+) -> Result<Vec<Address>, Error> {
+    let touched = pending.cache.accounts.keys().copied();
+
+    if !db.has_voter_index()? {
+        return Ok(touched.collect::<BTreeSet<_>>().into_iter().collect());
+    }
+
+    // votes record the balance at the time of the vote, which may have changed since
+    let mut voters = vote_changes(validator_contract, &pending.results, &pending.positions)
+        .into_iter()
+        .filter_map(|(voter, validator)| validator.map(|_| voter))
+        .collect::<BTreeSet<_>>();
+
+    for address in touched {
+        if voters.contains(&address) || db.get_voted_validator(address)?.is_none() {
+            continue;
+        }
+
+        let balance = |account: Option<revm::primitives::AccountInfo>| {
+            account.map(|account| account.balance).unwrap_or_default()
+        };
+        let committed = balance(db.basic_ref(address)?);
+        let pending = balance(
+            pending
+                .cache
+                .accounts
+                .get(&address)
+                .and_then(|account| account.account_info()),
+        );
+
+        if committed != pending {
+            voters.insert(address);
+        }
+    }
+
+    Ok(voters.into_iter().collect())
+}
+
+#[test]
+fn test_changed_voters() {
+    use alloy_sol_types::SolCall;
+    use revm::primitives::{address, SpecId, TransactTo, U256};
+
+    use crate::{
+        db::{CommitKey, GenesisInfo, PendingStateRef},
+        precompiles::PrecompileRegistry,
+        state_commit,
+        system_call::{
+            deploy_consensus, register_validator, transact, Consensus, SystemCallBlock,
+            SystemCaller, SYSTEM_ADDRESS,
+        },
+    };
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    // nothing is indexed without genesis info, even from height 0
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    state_commit::commit_to_db(&mut db, PendingCommit::new(CommitKey(0, 0))).expect("commit");
+    assert!(!db.has_voter_index().expect("voter index"));
+    db.close();
+
+    let path = tempfile::Builder::new()
+        .prefix("evm.mdb")
+        .tempdir()
+        .unwrap();
+
+    let mut db = PersistentDB::new(path.path().to_path_buf()).expect("database");
+    assert!(!db.has_voter_index().expect("voter index"));
+
+    let validators = [
+        address!("00000000000000000000000000000000000000a1"),
+        address!("00000000000000000000000000000000000000a2"),
+    ];
+    let voters = [
+        address!("00000000000000000000000000000000000000b1"),
+        address!("00000000000000000000000000000000000000b2"),
+        address!("00000000000000000000000000000000000000b3"),
+        address!("00000000000000000000000000000000000000b4"),
+    ];
+    let non_voter = address!("00000000000000000000000000000000000000c1");
+
+    let vote = |db: &PersistentDB, pending: &mut PendingCommit, consensus, voter, validator| {
+        let call = Consensus::voteCall { addr: validator };
+        assert!(transact(
+            db,
+            pending,
+            voter,
+            TransactTo::Call(consensus),
+            call.abi_encode().into()
+        )
+        .is_success());
+    };
+
+    // height 0: deploy, register validators and vote with all but the last voter
+    let mut pending = PendingCommit::new(CommitKey(0, 0));
+    state_commit::apply_rewards(
+        &mut db,
+        &mut pending,
+        voters
+            .iter()
+            .chain([&non_voter])
+            .map(|address| (*address, 1_000))
+            .collect(),
+    )
+    .expect("rewards");
+
+    let consensus = deploy_consensus(&db, &mut pending, SYSTEM_ADDRESS);
+    db.set_genesis_info(GenesisInfo {
+        deployer_account: SYSTEM_ADDRESS,
+        validator_contract: consensus,
+    });
+
+    for validator in validators {
+        register_validator(&db, &mut pending, consensus, validator);
+    }
+    vote(&db, &mut pending, consensus, voters[0], validators[0]);
+    vote(&db, &mut pending, consensus, voters[1], validators[0]);
+    vote(&db, &mut pending, consensus, voters[2], validators[1]);
+
+    state_commit::commit_to_db(&mut db, pending).expect("commit");
+    assert!(db.has_voter_index().expect("voter index"));
+    assert_eq!(
+        db.get_voted_validator(voters[0]).expect("vote"),
+        Some(validators[0])
+    );
+    assert_eq!(db.get_voted_validator(voters[3]).expect("vote"), None);
+
+    // height 1: change the balance of a voter and a non-voter, let the last voter vote
+    let mut pending = PendingCommit::new(CommitKey(1, 0));
+    state_commit::apply_rewards(
+        &mut db,
+        &mut pending,
+        [(voters[0], 500), (voters[3], 200), (non_voter, 300)].into(),
+    )
+    .expect("rewards");
+    vote(&db, &mut pending, consensus, voters[3], validators[1]);
+    // touched, but with an unchanged balance
+    state_commit::apply_rewards(&mut db, &mut pending, [(voters[1], 0)].into()).expect("rewards");
+
+    let changed = changed_voters(&db, &pending, consensus).expect("changed voters");
+    assert_eq!(changed, vec![voters[0], voters[3]]);
+
+    // updating only the changed voters gives the same vote balances as updating every
+    // touched account
+    let precompiles = PrecompileRegistry::default();
+    let system_caller = SystemCaller::new(&db, &precompiles);
+    let block = SystemCallBlock {
+        timestamp: U256::from(1),
+        coinbase: validators[0],
+        spec_id: SpecId::SHANGHAI,
+    };
+
+    let update_voters = |mut pending: PendingCommit, voters: Vec<Address>| {
+        for chunk in voters.chunks(UPDATE_VOTERS_CHUNK_SIZE) {
+            system_caller
+                .call(
+                    &mut pending,
+                    &block,
+                    consensus,
+                    Consensus::updateVotersCall {
+                        voters: chunk.to_vec(),
+                    },
+                )
+                .expect("updateVoters");
+        }
+
+        system_caller
+            .view(
+                PendingStateRef {
+                    db: &db,
+                    cache: Some(&pending.cache),
+                },
+                1,
+                block.spec_id,
+                consensus,
+                Consensus::getAllValidatorsCall {},
+            )
+            .expect("getAllValidators")
+            ._0
+            .into_iter()
+            .map(|validator| (validator.addr, validator.data.voteBalance))
+            .collect::<Vec<_>>()
+    };
+
+    let touched = pending.cache.accounts.keys().copied().collect();
+    let all = update_voters(pending.clone(), touched);
+    let targeted = update_voters(pending, changed);
+
+    assert_eq!(targeted, all);
+    assert_eq!(
+        targeted,
+        vec![
+            (validators[0], U256::from(2_500)),
+            (validators[1], U256::from(2_200)),
+        ]
+    );
+}
@@ -134,6 +134,7 @@ export class Validator implements Contracts.Validator.Validator {
 		const transactionBytes = await this.txPoolWorker.getTransactionBytes();
 
 		const validator = this.createTransactionValidator();
+		// Registers the validator contract with the ephemeral instance, the allocation only applies to height 0
 		await validator.getEvm().initializeGenesis(this.genesisInfo);
 		await validator.getEvm().prepareNextCommit({ commitKey });
 